@@ -0,0 +1,246 @@
+use chrono::NaiveDate;
+
+use crate::config::Config;
+use crate::export::{self, Filter, Format};
+use crate::import::{self, Source, Summary};
+use crate::report::{self, Grouping, Period};
+use crate::storage::{self, Lock, Storage};
+use crate::template;
+use crate::trash;
+use crate::{PersistedState, Time};
+
+// subcommands run without the tui, returns false when the args are meant for the app itself
+pub fn run(args: &[String]) -> Result<bool, Box<dyn std::error::Error>> {
+    match args.get(1).map(String::as_str) {
+        Some("report") => {
+            report_command(&args[2..])?;
+            Ok(true)
+        }
+        Some("export") => {
+            export_command(&args[2..])?;
+            Ok(true)
+        }
+        Some("import") => {
+            import_command(&args[2..])?;
+            Ok(true)
+        }
+        Some("add") => {
+            add_command(&args[2..])?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+fn open_storage() -> Result<Box<dyn Storage>, Box<dyn std::error::Error>> {
+    storage::open(&Config::load()?)
+}
+
+// the running app keeps its own copy of the timers and would save over the change
+fn lock(doing: &str) -> Result<Lock, Box<dyn std::error::Error>> {
+    Lock::acquire().map_err(|e| format!("{}, quit it before {}", e, doing).into())
+}
+
+fn report_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut period = Period::Day;
+    let mut grouping = Grouping::Label;
+    let mut offset = 0;
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--day" => period = Period::Day,
+            "--week" => period = Period::Week,
+            "--last" => offset = -1,
+            "--by" => {
+                let value = option_value(args, &mut i, arg)?;
+                grouping = Grouping::parse(value).ok_or_else(|| {
+                    format!("can't group by '{}', use label, project or tag", value)
+                })?;
+            }
+            other => return Err(format!("unknown report option: {}", other).into()),
+        }
+        i += 1;
+    }
+    let config = Config::load()?;
+    let timers = storage::open(&config)?
+        .load()?
+        .map(|persisted| trash::with_archived(&persisted.timers, &persisted.archive))
+        .unwrap_or_default();
+    let report = report::build(&timers, period, grouping, offset, Time::current_unix_time());
+    print!("{}", report::render_text(&report, config.time_format.as_ref()));
+    Ok(())
+}
+
+fn option_value<'a>(
+    args: &'a [String],
+    i: &mut usize,
+    name: &str,
+) -> Result<&'a str, Box<dyn std::error::Error>> {
+    *i += 1;
+    args.get(*i)
+        .map(String::as_str)
+        .ok_or_else(|| format!("{} needs a value", name).into())
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, Box<dyn std::error::Error>> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date '{}', expected YYYY-MM-DD", value).into())
+}
+
+fn export_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut format = None;
+    let mut from = None;
+    let mut to = None;
+    let mut label = None;
+    let mut tag = None;
+    let mut project = None;
+    let mut output = None;
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--format" => {
+                let value = option_value(args, &mut i, arg)?;
+                format = Some(
+                    Format::parse(value)
+                        .ok_or_else(|| format!("unknown export format: {}", value))?,
+                );
+            }
+            "--from" => from = Some(parse_date(option_value(args, &mut i, arg)?)?),
+            "--to" => to = Some(parse_date(option_value(args, &mut i, arg)?)?),
+            "--label" => label = Some(option_value(args, &mut i, arg)?.to_string()),
+            "--tag" => tag = Some(option_value(args, &mut i, arg)?.to_string()),
+            "--project" => project = Some(option_value(args, &mut i, arg)?.to_string()),
+            "--output" | "-o" => output = Some(option_value(args, &mut i, arg)?.to_string()),
+            other => return Err(format!("unknown export option: {}", other).into()),
+        }
+        i += 1;
+    }
+    // without --format, go by the output extension
+    let format = format.unwrap_or_else(|| output.as_deref().map_or(Format::Csv, Format::from_path));
+    let config = Config::load()?;
+    let timers = storage::open(&config)?
+        .load()?
+        .map(|persisted| trash::with_archived(&persisted.timers, &persisted.archive))
+        .unwrap_or_default();
+    let filter = Filter {
+        tag,
+        project,
+        ..Filter::from_dates(from, to, label)
+    };
+    let data = export::export(
+        &timers,
+        format,
+        &filter,
+        Time::current_unix_time(),
+        config.time_format.as_ref(),
+    )?;
+    match output {
+        Some(path) => std::fs::write(export::expand_home(&path), data)?,
+        None => print!("{}", data),
+    }
+    Ok(())
+}
+
+fn import_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut source = None;
+    let mut path = None;
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--format" => {
+                let value = option_value(args, &mut i, arg)?;
+                source = Some(
+                    Source::parse(value)
+                        .ok_or_else(|| format!("unknown import format: {}", value))?,
+                );
+            }
+            other if other.starts_with("--") => {
+                return Err(format!("unknown import option: {}", other).into())
+            }
+            other => path = Some(other.to_string()),
+        }
+        i += 1;
+    }
+    let path = path.ok_or("usage: stopwatch import <file> [--format timewarrior|toggl]")?;
+    let source = source
+        .or_else(|| Source::from_path(&path))
+        .ok_or("can't tell the format from the file name, pass --format")?;
+    let contents = std::fs::read_to_string(export::expand_home(&path))?;
+    let _lock = lock("importing")?;
+
+    let mut summary = Summary::default();
+    let entries = match source {
+        Source::Timewarrior => import::parse_timewarrior(&contents, &mut summary)?,
+        Source::Toggl => import::parse_toggl(&contents, &mut summary)?,
+    };
+    let storage = open_storage()?;
+    let mut state = storage
+        .load()?
+        .unwrap_or_else(|| PersistedState::empty(Time::current_unix_time()));
+    import::merge(&mut state, entries, &mut summary);
+    if summary.imported > 0 {
+        state.save_timestamp = Time::current_unix_time();
+        storage.save(&state)?;
+    }
+    println!(
+        "imported {} sessions ({} new timers), {} duplicates and {} unusable entries skipped",
+        summary.imported, summary.new_timers, summary.duplicates, summary.skipped
+    );
+    Ok(())
+}
+
+// the same timer ctrl+a would create, for scripts and cron jobs
+fn add_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let mut template = None;
+    let mut label = None;
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--template" => {
+                let name = option_value(args, &mut i, arg)?;
+                template = Some(
+                    template::find(&config.templates, name)
+                        .cloned()
+                        .ok_or_else(|| format!("no template named '{}' in config.toml", name))?,
+                );
+            }
+            "--label" => label = Some(option_value(args, &mut i, arg)?.to_string()),
+            other => return Err(format!("unknown add option: {}", other).into()),
+        }
+        i += 1;
+    }
+    let mut template = template.unwrap_or_default();
+    if label.is_some() {
+        template.label = label;
+    }
+
+    let _lock = lock("adding timers")?;
+    let storage = storage::open(&config)?;
+    let now = Time::current_unix_time();
+    let mut state = storage
+        .load()?
+        .unwrap_or_else(|| PersistedState::empty(now));
+    let mut timer = template.to_persisted(state.next_timer_id(), now);
+    // exclusive mode allows one running timer, let the app decide when to switch
+    if state.exclusive_mode && state.timers.iter().any(|t| t.running) {
+        timer.running = false;
+        timer.session_start = None;
+    }
+    let message = format!(
+        "added Timer {}: {} ({})",
+        state.timers.len() + 1,
+        report::timer_label(&timer),
+        if timer.running { "running" } else { "paused" }
+    );
+    state.timers.push(timer);
+    state.selected_timer = state.timers.len() - 1;
+    state.save_timestamp = now;
+    storage.save(&state)?;
+    println!("{}", message);
+    Ok(())
+}
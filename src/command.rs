@@ -0,0 +1,614 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Padding},
+    Frame,
+};
+
+use crate::duration::{parse_duration, Adjustment};
+use crate::export::Format;
+use crate::fuzzy;
+use crate::input::LineInput;
+use crate::keymap::{Action, Keymap};
+use crate::report::Period;
+use crate::tags::TagFilter;
+use crate::timefmt::TimeFormat;
+use crate::view::View;
+
+// which timers a command is for, numbers are the ones in the "Timer N" titles
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Target {
+    Selected,
+    Timer(usize), // index into the grid
+    All,
+}
+
+pub enum Command {
+    Run(Target, Action),              // select the timer, then do what the key does
+    Add(Option<String>, Option<u64>), // template or label, countdown length
+    Label(Target, String),
+    Tags(Target, String),
+    Project(Target, String),
+    Adjust(Target, Adjustment),
+    Reset(Target),
+    Pause(Target),
+    Resume(Target),
+    Select(usize),
+    Move(Target, isize),   // one place back or forward
+    MoveTo(Target, usize), // to a grid index
+    Exclusive(Option<bool>),
+    Filter(Option<TagFilter>),
+    Report(Option<Period>),
+    Export(Option<Format>, Option<String>),
+    Fps(u64),
+    View(Option<View>), // None goes to the next one
+    Format(Target, Option<TimeFormat>), // None goes back to time_format from config.toml
+}
+
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub description: &'static str,
+    pub action: Option<Action>, // the keymap action it matches, for showing its key
+}
+
+impl CommandSpec {
+    // palette entries run straight away unless something has to be typed after them
+    pub fn takes_arguments(&self) -> bool {
+        self.usage
+            .split_whitespace()
+            .any(|arg| !arg.starts_with('['))
+    }
+}
+
+const fn spec(
+    name: &'static str,
+    usage: &'static str,
+    description: &'static str,
+    action: Option<Action>,
+) -> CommandSpec {
+    CommandSpec {
+        name,
+        usage,
+        description,
+        action,
+    }
+}
+
+pub const COMMANDS: [CommandSpec; 29] = [
+    spec(
+        "add",
+        "[TEMPLATE|LABEL] [DURATION]",
+        "Add a timer, a duration makes it a countdown",
+        Some(Action::AddTimer),
+    ),
+    spec(
+        "label",
+        "[N] TEXT",
+        "Set the label of a timer",
+        Some(Action::EditLabel),
+    ),
+    spec(
+        "tags",
+        "[N] TAGS",
+        "Set the tags of a timer",
+        Some(Action::EditTags),
+    ),
+    spec(
+        "project",
+        "[N] NAME",
+        "Set the project of a timer",
+        Some(Action::EditProject),
+    ),
+    spec(
+        "adjust",
+        "[N] +15m|-1h|=2:45:00",
+        "Adjust the time of a timer",
+        Some(Action::AdjustTime),
+    ),
+    spec("reset", "[N|all]", "Reset timers", Some(Action::ResetTimer)),
+    spec(
+        "delete",
+        "[N]",
+        "Move a timer to the trash",
+        Some(Action::DeleteTimer),
+    ),
+    spec(
+        "archive",
+        "[N]",
+        "Archive a timer",
+        Some(Action::ArchiveTimer),
+    ),
+    spec("pause", "[N|all]", "Pause timers", None),
+    spec("resume", "[N|all]", "Resume timers", None),
+    spec(
+        "toggle",
+        "[N]",
+        "Pause or resume a timer",
+        Some(Action::TogglePause),
+    ),
+    spec("select", "N", "Select a timer", None),
+    spec(
+        "move",
+        "[N] left|right|POSITION",
+        "Move a timer in the grid",
+        None,
+    ),
+    spec("next", "", "Select the next timer", Some(Action::NextTimer)),
+    spec(
+        "find",
+        "",
+        "Jump to a timer by label, tag or number",
+        Some(Action::FindTimer),
+    ),
+    spec(
+        "exclusive",
+        "[on|off]",
+        "Only let one timer run at a time",
+        Some(Action::ToggleExclusive),
+    ),
+    spec(
+        "filter",
+        "[#TAG|@PROJECT]",
+        "Only show matching timers, nothing shows all",
+        Some(Action::Filter),
+    ),
+    spec(
+        "notes",
+        "[N]",
+        "Edit the notes of a timer",
+        Some(Action::TimerNotes),
+    ),
+    spec(
+        "sessions",
+        "[N]",
+        "Notes for a session of a timer",
+        Some(Action::SessionNotes),
+    ),
+    spec(
+        "report",
+        "[day|week]",
+        "Show the time report",
+        Some(Action::ShowReport),
+    ),
+    spec(
+        "export",
+        "[FORMAT] [PATH]",
+        "Export timers to a file",
+        Some(Action::Export),
+    ),
+    spec(
+        "view",
+        "[grid|table|focus|sidebar]",
+        "Switch how the timers are laid out",
+        Some(Action::CycleView),
+    ),
+    spec(
+        "format",
+        "[N] FORMAT|default",
+        "Show a timer's time as HH:MM:SS, %Dd %H:%M:%S, %.1T, ~ hides zero units",
+        None,
+    ),
+    spec("fps", "N", "Set the UI frame rate (10-100)", None),
+    spec("undo", "", "Undo the last change", Some(Action::Undo)),
+    spec("redo", "", "Redo what was undone", Some(Action::Redo)),
+    spec(
+        "shelf",
+        "",
+        "Browse archive and trash",
+        Some(Action::BrowseShelf),
+    ),
+    spec("help", "", "Toggle help", Some(Action::ToggleHelp)),
+    spec("quit", "", "Quit", Some(Action::Quit)),
+];
+
+fn find(name: &str) -> Option<&'static CommandSpec> {
+    let name = match name {
+        "q" => "quit",
+        "rm" => "delete",
+        name => name,
+    };
+    COMMANDS.iter().find(|spec| spec.name == name)
+}
+
+pub fn names() -> Vec<String> {
+    COMMANDS.iter().map(|spec| spec.name.to_string()).collect()
+}
+
+// a leading number picks the timer when something follows it or nothing has to
+fn split_target(args: &str, timer_count: usize, allow_all: bool) -> Result<(Target, &str), String> {
+    let (first, rest) = args.split_once(' ').unwrap_or((args, ""));
+    if allow_all && first.eq_ignore_ascii_case("all") {
+        return Ok((Target::All, rest.trim()));
+    }
+    match first.parse::<usize>() {
+        Ok(n) if (1..=timer_count).contains(&n) => Ok((Target::Timer(n - 1), rest.trim())),
+        Ok(n) => Err(format!("there is no Timer {}", n)),
+        Err(_) => Ok((Target::Selected, args)),
+    }
+}
+
+fn no_arguments(name: &str, args: &str) -> Result<(), String> {
+    if args.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} takes no arguments", name))
+    }
+}
+
+// "label 3 review", ":reset all", "toggle-pause"; timer_count checks the timer numbers
+pub fn parse(line: &str, timer_count: usize) -> Result<Command, String> {
+    let line = line.trim().trim_start_matches(':').trim_start();
+    let (name, args) = line.split_once(' ').unwrap_or((line, ""));
+    let args = args.trim();
+    let name = name.to_ascii_lowercase();
+    if name.is_empty() {
+        return Err("type a command, ctrl+k lists them".to_string());
+    }
+    let Some(spec) = find(&name) else {
+        // every keymap action works by its config name too
+        return match Action::ALL.iter().find(|action| action.name() == name) {
+            Some(action) => {
+                no_arguments(&name, args).map(|_| Command::Run(Target::Selected, *action))
+            }
+            None => Err(format!("unknown command '{}'", name)),
+        };
+    };
+    let targeted = |allow_all| split_target(args, timer_count, allow_all);
+    let command = match spec.name {
+        "add" => {
+            let (label, duration) = match args.rsplit_once(' ') {
+                Some((label, last)) => (label.trim(), last),
+                None => ("", args),
+            };
+            match parse_duration(duration) {
+                Ok(seconds) if seconds > 0 => Command::Add(
+                    Some(label.to_string()).filter(|l| !l.is_empty()),
+                    Some(seconds),
+                ),
+                _ => Command::Add(Some(args.to_string()).filter(|a| !a.is_empty()), None),
+            }
+        }
+        "label" | "tags" | "project" | "adjust" => {
+            let (target, text) = targeted(false)?;
+            match spec.name {
+                // without the text it opens the prompt the key would
+                _ if text.is_empty() => Command::Run(target, spec.action.expect("has a key")),
+                "label" => Command::Label(target, text.to_string()),
+                "tags" => Command::Tags(target, text.to_string()),
+                "project" => Command::Project(target, text.to_string()),
+                _ => Command::Adjust(target, Adjustment::parse(text)?),
+            }
+        }
+        "reset" | "pause" | "resume" => {
+            let (target, rest) = targeted(true)?;
+            no_arguments(spec.name, rest)?;
+            match spec.name {
+                "reset" => Command::Reset(target),
+                "pause" => Command::Pause(target),
+                _ => Command::Resume(target),
+            }
+        }
+        "select" => match targeted(false)? {
+            (Target::Timer(idx), "") => Command::Select(idx),
+            _ => return Err("select needs a timer number".to_string()),
+        },
+        "move" => {
+            let (target, place) = targeted(false)?;
+            match place.to_ascii_lowercase().as_str() {
+                "left" | "back" => Command::Move(target, -1),
+                "right" | "forward" => Command::Move(target, 1),
+                place => match place.parse::<usize>() {
+                    Ok(n) if (1..=timer_count).contains(&n) => Command::MoveTo(target, n - 1),
+                    _ => return Err("move takes left, right or a timer position".to_string()),
+                },
+            }
+        }
+        "exclusive" => Command::Exclusive(match args.to_ascii_lowercase().as_str() {
+            "" => None,
+            "on" | "true" | "yes" => Some(true),
+            "off" | "false" | "no" => Some(false),
+            other => return Err(format!("exclusive is on or off, not '{}'", other)),
+        }),
+        "filter" => Command::Filter(TagFilter::parse(args)),
+        "report" => Command::Report(match args.to_ascii_lowercase().as_str() {
+            "" => None,
+            "day" | "today" => Some(Period::Day),
+            "week" => Some(Period::Week),
+            other => return Err(format!("report is for a day or a week, not '{}'", other)),
+        }),
+        "export" => {
+            let (first, rest) = args.split_once(' ').unwrap_or((args, ""));
+            match Format::parse(first) {
+                Some(format) => Command::Export(
+                    Some(format),
+                    Some(rest.trim().to_string()).filter(|p| !p.is_empty()),
+                ),
+                None => Command::Export(None, Some(args.to_string()).filter(|p| !p.is_empty())),
+            }
+        }
+        "view" if args.is_empty() => Command::View(None),
+        "view" => match View::parse(args) {
+            Some(view) => Command::View(Some(view)),
+            None => return Err(format!("there is no '{}' view", args)),
+        },
+        "format" => {
+            let (target, text) = targeted(false)?;
+            match text {
+                "" | "default" => Command::Format(target, None),
+                text => Command::Format(target, Some(TimeFormat::parse(text)?)),
+            }
+        }
+        "fps" => match args.parse::<u64>() {
+            Ok(fps) if (10..=100).contains(&fps) => Command::Fps(fps),
+            _ => return Err("fps takes a number from 10 to 100".to_string()),
+        },
+        _ => {
+            let (target, rest) = targeted(false)?;
+            no_arguments(spec.name, rest)?;
+            Command::Run(target, spec.action.expect("every other command has a key"))
+        }
+    };
+    Ok(command)
+}
+
+// up and down on the command line go through earlier commands like a shell
+#[derive(Default)]
+pub struct CommandHistory {
+    lines: Vec<String>,
+    browsing: Option<usize>,
+}
+
+impl CommandHistory {
+    pub fn push(&mut self, line: &str) {
+        self.browsing = None;
+        if !line.trim().is_empty() && self.lines.last().map(String::as_str) != Some(line) {
+            self.lines.push(line.to_string());
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.browsing = None;
+    }
+
+    // older with -1, newer with 1, None once past the newest
+    pub fn step(&mut self, delta: isize) -> Option<&str> {
+        let next = match self.browsing {
+            None if delta < 0 => self.lines.len().checked_sub(1),
+            None => None,
+            Some(i) => i
+                .checked_add_signed(delta)
+                .filter(|i| *i < self.lines.len()),
+        };
+        // stay on the oldest line instead of falling off the top
+        if next.is_none() && delta < 0 && self.browsing.is_some() {
+            return self.browsing.map(|i| self.lines[i].as_str());
+        }
+        self.browsing = next;
+        next.map(|i| self.lines[i].as_str())
+    }
+}
+
+pub enum PaletteResult {
+    Run(&'static CommandSpec),
+    Close,
+    Open,
+}
+
+// ctrl+k, every command with its key, narrowed down as you type
+pub struct Palette {
+    pub query: LineInput,
+    selected: usize,
+}
+
+impl Palette {
+    pub fn new() -> Self {
+        Self {
+            query: LineInput::new(64),
+            selected: 0,
+        }
+    }
+
+    fn matches(&self) -> Vec<&'static CommandSpec> {
+        let query = self.query.text();
+        let mut scored: Vec<(i64, &'static CommandSpec)> = COMMANDS
+            .iter()
+            .filter_map(|spec| {
+                // the name counts most, the description helps find things by what they do
+                let by_name = fuzzy::score(query, spec.name).map(|score| score * 4);
+                let by_description = fuzzy::score(query, spec.description);
+                by_name.max(by_description).map(|score| (score, spec))
+            })
+            .collect();
+        if !query.is_empty() {
+            scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        }
+        scored.into_iter().map(|(_, spec)| spec).collect()
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PaletteResult {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return PaletteResult::Close,
+            KeyCode::Enter => {
+                return match self.matches().get(self.selected) {
+                    Some(spec) => PaletteResult::Run(spec),
+                    None => PaletteResult::Open,
+                }
+            }
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('p') if ctrl => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Tab => self.selected += 1,
+            KeyCode::Char('n') if ctrl => self.selected += 1,
+            _ => {
+                if self.query.handle_key(key) {
+                    self.selected = 0;
+                }
+            }
+        }
+        self.selected = self.selected.min(self.matches().len().saturating_sub(1));
+        PaletteResult::Open
+    }
+
+    pub fn draw(&self, frame: &mut Frame, keymap: &Keymap) {
+        let matches = self.matches();
+        let area = frame.area();
+        let width = (area.width * 2 / 3).max(50).min(area.width);
+        let height = (COMMANDS.len() as u16 + 4)
+            .min(area.height * 2 / 3)
+            .max(6)
+            .min(area.height);
+        let palette_area = Rect::new((area.width - width) / 2, area.height / 6, width, height);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Green))
+            .title(" Commands ")
+            .title_bottom(Line::from(" enter run · esc close ").right_aligned())
+            .padding(Padding::horizontal(1))
+            .style(Style::default().fg(Color::Gray).bg(Color::Black));
+        let inner = block.inner(palette_area);
+        frame.render_widget(Clear, palette_area);
+        frame.render_widget(block, palette_area);
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Min(0),
+            ])
+            .split(inner);
+        self.query.draw(frame, rows[0], "> ", Alignment::Left);
+
+        let usage_width = matches
+            .iter()
+            .map(|spec| spec.name.len() + spec.usage.len() + 1)
+            .max()
+            .unwrap_or(0);
+        let items: Vec<ListItem> = matches
+            .iter()
+            .map(|spec| {
+                let keys = spec
+                    .action
+                    .map(|action| keymap.keys(action))
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|key| key.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" / ");
+                let usage = format!("{} {}", spec.name, spec.usage);
+                let text = format!("{:<usage_width$}  {}", usage, spec.description);
+                let pad = (rows[2].width as usize)
+                    .saturating_sub(text.chars().count() + keys.chars().count() + 1);
+                ListItem::new(format!("{}{} {}", text, " ".repeat(pad), keys))
+            })
+            .collect();
+        let empty = items.is_empty();
+        let list = List::new(if empty {
+            vec![ListItem::new("no command matches")]
+        } else {
+            items
+        })
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut list_state = ListState::default();
+        list_state.select((!empty).then_some(self.selected));
+        frame.render_stateful_widget(list, rows[2], &mut list_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_targets() {
+        assert!(matches!(
+            parse(":label 2 code review", 3),
+            Ok(Command::Label(Target::Timer(1), label)) if label == "code review"
+        ));
+        assert!(matches!(
+            parse("label review", 3),
+            Ok(Command::Label(Target::Selected, label)) if label == "review"
+        ));
+        assert!(matches!(
+            parse("reset all", 3),
+            Ok(Command::Reset(Target::All))
+        ));
+        assert!(matches!(
+            parse("label", 3),
+            Ok(Command::Run(Target::Selected, Action::EditLabel))
+        ));
+        assert_eq!(
+            parse("label 4 x", 3).err().as_deref(),
+            Some("there is no Timer 4")
+        );
+        // only reset, pause and resume take all
+        assert!(matches!(
+            parse("label all x", 3),
+            Ok(Command::Label(Target::Selected, label)) if label == "all x"
+        ));
+    }
+
+    #[test]
+    fn parses_arguments() {
+        assert!(matches!(
+            parse("add tea 5m", 0),
+            Ok(Command::Add(Some(label), Some(300))) if label == "tea"
+        ));
+        assert!(matches!(
+            parse("add deep work", 0),
+            Ok(Command::Add(Some(label), None)) if label == "deep work"
+        ));
+        assert!(matches!(
+            parse("adjust 1 +15m", 2),
+            Ok(Command::Adjust(Target::Timer(0), Adjustment::Add(900)))
+        ));
+        assert!(matches!(
+            parse("move 2 left", 2),
+            Ok(Command::Move(Target::Timer(1), -1))
+        ));
+        assert!(matches!(
+            parse("move 1 3", 3),
+            Ok(Command::MoveTo(Target::Timer(0), 2))
+        ));
+        assert!(matches!(
+            parse("exclusive off", 0),
+            Ok(Command::Exclusive(Some(false)))
+        ));
+        assert!(matches!(
+            parse("export ics ~/week.ics", 0),
+            Ok(Command::Export(Some(Format::Ical), Some(path))) if path == "~/week.ics"
+        ));
+        assert!(matches!(
+            parse("format default", 0),
+            Ok(Command::Format(Target::Selected, None))
+        ));
+        assert!(matches!(parse("fps 30", 0), Ok(Command::Fps(30))));
+    }
+
+    #[test]
+    fn keymap_actions_work_by_name() {
+        assert!(matches!(
+            parse("toggle-pause", 1),
+            Ok(Command::Run(Target::Selected, Action::TogglePause))
+        ));
+        assert!(matches!(
+            parse("q", 1),
+            Ok(Command::Run(Target::Selected, Action::Quit))
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_commands() {
+        assert!(parse("", 1).is_err());
+        assert!(parse("fly", 1).is_err());
+        assert!(parse("toggle-pause now", 1).is_err());
+        assert!(parse("select", 1).is_err());
+        assert!(parse("move 1 up", 1).is_err());
+        assert!(parse("exclusive maybe", 1).is_err());
+        assert!(parse("fps 5", 1).is_err());
+        assert!(parse("adjust +x", 1).is_err());
+        assert!(parse("format %Q", 1).is_err());
+    }
+}
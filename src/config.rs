@@ -0,0 +1,104 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::digits::Digits;
+use crate::storage;
+use crate::template::Template;
+use crate::timefmt::TimeFormat;
+
+#[derive(Clone, Copy, Deserialize, Default, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    #[default]
+    Toml,
+    Sqlite,
+}
+
+// actions that can be set up to ask before they happen
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmAction {
+    Quit,
+    Reset,
+    Delete,
+    Purge,
+    #[serde(skip_deserializing)]
+    ResetAll, // asks when reset does
+}
+
+// user settings from config.toml next to the executable, every field is optional
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct Config {
+    pub storage: StorageKind,
+    pub confirm: Vec<ConfirmAction>, // `confirm = []` turns every prompt off
+    pub trash_days: u64,             // deleted timers are purged after this long, 0 keeps them
+    pub templates: Vec<Template>,    // `[[templates]]` offered when adding a timer
+    pub keys: BTreeMap<String, toml::Value>, // `[keys]` overrides, `add-timer = "ctrl+n"`
+    pub digits: Digits,                  // block, braille or plain for the time in the boxes
+    pub time_format: Option<TimeFormat>, // "%Dd %H:%M:%S", "HH:MM:SS", "%.1T"
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            storage: StorageKind::default(),
+            confirm: vec![
+                ConfirmAction::Quit,
+                ConfirmAction::Reset,
+                ConfirmAction::Delete,
+                ConfirmAction::Purge,
+            ],
+            trash_days: 30,
+            templates: Vec::new(),
+            keys: BTreeMap::new(),
+            digits: Digits::default(),
+            time_format: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = storage::data_dir()?.join("config.toml");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+
+    // the app starts anyway when the file is broken, but only with the storage backend the
+    // file asks for, or it would save into a different place than the cli reads from
+    pub fn load_for_app() -> Result<(Self, Option<String>), Box<dyn std::error::Error>> {
+        match Self::load() {
+            Ok(config) => Ok((config, None)),
+            Err(e) => {
+                let storage = Self::storage_setting().map_err(|_| e.to_string())?;
+                let config = Self {
+                    storage,
+                    ..Self::default()
+                };
+                Ok((config, Some(format!("config.toml ignored: {}", e))))
+            }
+        }
+    }
+
+    fn storage_setting() -> Result<StorageKind, Box<dyn std::error::Error>> {
+        let contents = fs::read_to_string(storage::data_dir()?.join("config.toml"))?;
+        let table: toml::Table = contents.parse()?;
+        Ok(match table.get("storage") {
+            Some(value) => value.clone().try_into()?,
+            None => StorageKind::default(),
+        })
+    }
+
+    pub fn needs_confirmation(&self, action: ConfirmAction) -> bool {
+        let action = match action {
+            ConfirmAction::ResetAll => ConfirmAction::Reset,
+            action => action,
+        };
+        self.confirm.contains(&action)
+    }
+}
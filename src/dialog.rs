@@ -0,0 +1,66 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+pub enum DialogResult {
+    Confirmed,
+    Cancelled,
+    Pending,
+}
+
+// yes/no modal drawn on top of everything, the main loop keeps running underneath
+pub struct Dialog<A> {
+    pub message: String,
+    pub action: A, // what to do once confirmed
+}
+
+impl<A> Dialog<A> {
+    pub fn new(message: String, action: A) -> Self {
+        Self { message, action }
+    }
+
+    pub fn handle_key(&self, key: KeyEvent) -> DialogResult {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => DialogResult::Confirmed,
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => DialogResult::Cancelled,
+            _ => DialogResult::Pending,
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let text = vec![
+            Line::from(self.message.as_str()),
+            Line::from(""),
+            Line::from(vec![
+                Span::raw("Are you sure? "),
+                Span::styled("Y", Style::default().fg(Color::Green)),
+                Span::styled("/", Style::default().fg(Color::Gray)),
+                Span::styled("N", Style::default().fg(Color::Red)),
+            ]),
+        ];
+        let prompt_block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray))
+            .title(" Confirmation ");
+        let prompt_paragraph = Paragraph::new(text)
+            .block(prompt_block)
+            .centered()
+            .wrap(Wrap { trim: true })
+            .style(Style::default().fg(Color::Gray).bg(Color::Black));
+
+        let rect_width = (area.width / 2).max(30).min(area.width);
+        let rect_height = (area.height / 4).max(5).min(area.height);
+        let x_pos = (area.width - rect_width) / 2;
+        let y_pos = (area.height - rect_height) / 2;
+        let prompt_area = Rect::new(x_pos, y_pos, rect_width, rect_height);
+
+        frame.render_widget(Clear, prompt_area);
+        frame.render_widget(prompt_paragraph, prompt_area);
+    }
+}
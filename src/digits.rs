@@ -0,0 +1,135 @@
+use serde::Deserialize;
+
+// how the time in a timer box is drawn, `digits = "braille"` in config.toml
+#[derive(Clone, Copy, Deserialize, Default, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Digits {
+    #[default]
+    Block, // as big as the box allows, down to half blocks and braille
+    Braille, // always the small dotted font
+    Plain,
+}
+
+// 3x5 pixel font, ':' '.' and ' ' are narrower
+fn glyph(c: char) -> Option<[&'static str; 5]> {
+    Some(match c {
+        '0' => ["###", "#.#", "#.#", "#.#", "###"],
+        '1' => [".#.", "##.", ".#.", ".#.", "###"],
+        '2' => ["###", "..#", "###", "#..", "###"],
+        '3' => ["###", "..#", "###", "..#", "###"],
+        '4' => ["#.#", "#.#", "###", "..#", "..#"],
+        '5' => ["###", "#..", "###", "..#", "###"],
+        '6' => ["###", "#..", "###", "#.#", "###"],
+        '7' => ["###", "..#", "..#", "..#", "..#"],
+        '8' => ["###", "#.#", "###", "#.#", "###"],
+        '9' => ["###", "#.#", "###", "..#", "###"],
+        'd' => ["..#", "..#", "###", "#.#", "###"],
+        'h' => ["#..", "#..", "###", "#.#", "#.#"],
+        'm' => ["...", "...", "###", "###", "#.#"],
+        's' => ["...", "###", "##.", "..#", "###"],
+        '+' => ["...", ".#.", "###", ".#.", "..."],
+        '-' => ["...", "...", "###", "...", "..."],
+        ':' => [".", "#", ".", "#", "."],
+        '.' => [".", ".", ".", ".", "#"],
+        ' ' => [".", ".", ".", ".", "."],
+        _ => return None,
+    })
+}
+
+// the text as rows of pixels with a blank column between glyphs, None for a char the font lacks
+fn pixels(text: &str) -> Option<Vec<Vec<bool>>> {
+    let mut rows = vec![Vec::new(); 5];
+    for (i, c) in text.chars().enumerate() {
+        let glyph = glyph(c)?;
+        for (row, line) in rows.iter_mut().zip(glyph) {
+            if i > 0 {
+                row.push(false);
+            }
+            row.extend(line.chars().map(|p| p == '#'));
+        }
+    }
+    Some(rows)
+}
+
+// every pixel becomes a block of scale rows and twice as many columns, cells are about twice as tall as wide
+fn blocks(pixels: &[Vec<bool>], scale: usize) -> Vec<String> {
+    pixels
+        .iter()
+        .flat_map(|row| {
+            let line: String = row
+                .iter()
+                .map(|on| if *on { "█" } else { " " }.repeat(scale * 2))
+                .collect();
+            std::iter::repeat_n(line, scale)
+        })
+        .collect()
+}
+
+// two pixel rows per line with half blocks
+fn half_blocks(pixels: &[Vec<bool>]) -> Vec<String> {
+    pixels
+        .chunks(2)
+        .map(|pair| {
+            (0..pair[0].len())
+                .map(|x| {
+                    let top = pair[0][x];
+                    let bottom = pair.get(1).is_some_and(|row| row[x]);
+                    match (top, bottom) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// 2x4 pixels per braille cell
+fn braille(pixels: &[Vec<bool>]) -> Vec<String> {
+    const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+    let width = pixels[0].len();
+    pixels
+        .chunks(4)
+        .map(|rows| {
+            (0..width.div_ceil(2))
+                .map(|cx| {
+                    let mut bits = 0;
+                    for (dy, row) in rows.iter().enumerate() {
+                        for (dx, dot) in DOTS[dy].iter().enumerate() {
+                            if row.get(cx * 2 + dx).copied().unwrap_or(false) {
+                                bits |= dot;
+                            }
+                        }
+                    }
+                    char::from_u32(0x2800 + bits).unwrap_or(' ')
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// the biggest rendering of text that fits in width x height cells, None when plain text has to do
+pub fn render(text: &str, digits: Digits, width: u16, height: u16) -> Option<Vec<String>> {
+    if digits == Digits::Plain {
+        return None;
+    }
+    let pixels = pixels(text)?;
+    let (width, height) = (width as usize, height as usize);
+    let pixel_width = pixels[0].len();
+    let fits = |lines: &[String]| {
+        lines.len() <= height && lines.iter().all(|line| line.chars().count() <= width)
+    };
+    if digits == Digits::Block {
+        let scale = (width / (pixel_width * 2).max(1)).min(height / 5);
+        if scale > 0 {
+            return Some(blocks(&pixels, scale));
+        }
+        let lines = half_blocks(&pixels);
+        if fits(&lines) {
+            return Some(lines);
+        }
+    }
+    Some(braille(&pixels)).filter(|lines| fits(lines))
+}
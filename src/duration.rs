@@ -0,0 +1,139 @@
+// parsing for durations typed by the user, "1h30m", "90s", "2:45:00" or "45:00"
+pub fn parse_duration(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("empty duration".to_string());
+    }
+    if input.contains(':') {
+        return parse_clock(input);
+    }
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c.to_ascii_lowercase() {
+            'd' => 86400,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            c if c.is_whitespace() => continue,
+            other => return Err(format!("unknown unit '{}'", other)),
+        };
+        let value: u64 = digits
+            .parse()
+            .map_err(|_| format!("missing number before '{}'", c))?;
+        total = total.saturating_add(value.saturating_mul(unit));
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        return Err(format!("'{}' needs a unit (d, h, m or s)", digits));
+    }
+    Ok(total)
+}
+
+// h:mm:ss or mm:ss
+fn parse_clock(input: &str) -> Result<u64, String> {
+    let parts = input
+        .split(':')
+        .map(|part| part.trim().parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid time '{}'", input))?;
+    let total = match parts[..] {
+        [h, m, s] if m < 60 && s < 60 => h
+            .checked_mul(3600)
+            .and_then(|total| total.checked_add(m * 60 + s)),
+        [m, s] if s < 60 => m.checked_mul(60).and_then(|total| total.checked_add(s)),
+        _ => None,
+    };
+    total.ok_or_else(|| format!("invalid time '{}'", input))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Adjustment {
+    Add(u64),
+    Subtract(u64),
+    Set(u64),
+}
+
+impl Adjustment {
+    // "+15m", "-1h30m", "=2:45:00", no prefix means "="
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        if let Some(rest) = input.strip_prefix('+') {
+            Ok(Self::Add(parse_duration(rest)?))
+        } else if let Some(rest) = input.strip_prefix('-') {
+            Ok(Self::Subtract(parse_duration(rest)?))
+        } else {
+            Ok(Self::Set(parse_duration(
+                input.strip_prefix('=').unwrap_or(input),
+            )?))
+        }
+    }
+
+    pub fn apply(self, current: u64) -> u64 {
+        match self {
+            Self::Add(seconds) => current.saturating_add(seconds),
+            Self::Subtract(seconds) => current.saturating_sub(seconds),
+            Self::Set(seconds) => seconds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_units() {
+        assert_eq!(parse_duration("90s"), Ok(90));
+        assert_eq!(parse_duration("1h30m"), Ok(5400));
+        assert_eq!(parse_duration("1d 2h"), Ok(93600));
+        assert_eq!(parse_duration(" 2M "), Ok(120));
+    }
+
+    #[test]
+    fn parses_clock_times() {
+        assert_eq!(parse_duration("45:00"), Ok(2700));
+        assert_eq!(parse_duration("2:45:00"), Ok(9900));
+        assert_eq!(parse_duration("100:00:01"), Ok(360001));
+    }
+
+    #[test]
+    fn rejects_bad_durations() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("15").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("3x").is_err());
+        assert!(parse_duration("1:60").is_err());
+        assert!(parse_duration("1:60:00").is_err());
+        assert!(parse_duration("1:2:3:4").is_err());
+        assert!(parse_duration("a:00").is_err());
+    }
+
+    #[test]
+    fn huge_values_dont_overflow() {
+        assert!(parse_duration("99999999999999999:00:00").is_err());
+        assert!(parse_duration("999999999999999999:00").is_err());
+        assert_eq!(parse_duration("99999999999999999h"), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn parses_adjustments() {
+        assert_eq!(Adjustment::parse("+15m"), Ok(Adjustment::Add(900)));
+        assert_eq!(Adjustment::parse("-1h30m"), Ok(Adjustment::Subtract(5400)));
+        assert_eq!(Adjustment::parse("=2:45:00"), Ok(Adjustment::Set(9900)));
+        assert_eq!(Adjustment::parse("10m"), Ok(Adjustment::Set(600)));
+        assert!(Adjustment::parse("=99999999999999999:00:00").is_err());
+    }
+
+    #[test]
+    fn applies_adjustments() {
+        assert_eq!(Adjustment::Add(60).apply(30), 90);
+        assert_eq!(Adjustment::Subtract(60).apply(30), 0);
+        assert_eq!(Adjustment::Set(60).apply(30), 60);
+        assert_eq!(Adjustment::Add(1).apply(u64::MAX), u64::MAX);
+    }
+}
@@ -0,0 +1,700 @@
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::report;
+use crate::session::{self, Session};
+use crate::tags::TagFilter;
+use crate::timefmt::TimeFormat;
+use crate::PersistedTimer;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Csv,
+    Json,
+    Ndjson,
+    Timewarrior, // timewarrior's data files, `inc <start> - <end> # tags`
+    Org,         // org-mode CLOCK lines under one heading per label
+    Ical,        // one VEVENT per session
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            "ndjson" | "jsonl" => Some(Self::Ndjson),
+            "timewarrior" | "timew" | "data" => Some(Self::Timewarrior),
+            "org" => Some(Self::Org),
+            "ics" | "ical" | "icalendar" => Some(Self::Ical),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Ndjson => "ndjson",
+            Self::Timewarrior => "data",
+            Self::Org => "org",
+            Self::Ical => "ics",
+        }
+    }
+
+    // guess from the file extension, csv when there's nothing to go on
+    pub fn from_path(path: &str) -> Self {
+        path.rsplit_once('.')
+            .and_then(|(_, ext)| Self::parse(ext))
+            .unwrap_or(Self::Csv)
+    }
+}
+
+#[derive(Default)]
+pub struct Filter {
+    pub from: Option<u64>, // inclusive, UNIX seconds
+    pub to: Option<u64>,   // exclusive
+    pub label: Option<String>,
+    pub tag: Option<String>,
+    pub project: Option<String>,
+}
+
+impl Filter {
+    // dates are local calendar days, `to` includes the whole day
+    pub fn from_dates(
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        label: Option<String>,
+    ) -> Self {
+        Self {
+            from: from.map(report::local_midnight),
+            to: to
+                .and_then(|date| date.succ_opt())
+                .map(report::local_midnight),
+            label,
+            tag: None,
+            project: None,
+        }
+    }
+
+    fn matches(&self, timer: &PersistedTimer) -> bool {
+        let label = self
+            .label
+            .as_ref()
+            .is_none_or(|wanted| report::timer_label(timer).eq_ignore_ascii_case(wanted));
+        let tag = self.tag.as_ref().is_none_or(|wanted| {
+            TagFilter::Tag(wanted.clone()).matches(&timer.tags, timer.project.as_deref())
+        });
+        let project = self.project.as_ref().is_none_or(|wanted| {
+            TagFilter::Project(wanted.clone()).matches(&timer.tags, timer.project.as_deref())
+        });
+        label && tag && project
+    }
+
+    // cut the session to the requested range, None if nothing is left
+    fn clip(&self, session: &Session) -> Option<Session> {
+        let from = self.from.unwrap_or(0);
+        let to = self.to.unwrap_or(u64::MAX);
+        if session.is_adjustment() {
+            return (from..to).contains(&session.start).then(|| session.clone());
+        }
+        let start = session.start.max(from);
+        let end = session.end.min(to);
+        (end > start).then(|| Session {
+            start,
+            end,
+            ..session.clone()
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct ExportSession {
+    start: String,
+    end: String,
+    duration_seconds: u64,
+    duration: String, // in the timer's time format
+    adjustment_seconds: i64,
+    note: String,
+    #[serde(skip)]
+    interval: Session,
+    #[serde(skip)]
+    recorded_start: u64, // before the filter clipped it
+}
+
+#[derive(Serialize)]
+struct ExportTimer {
+    timer_id: usize,
+    label: String,
+    project: Option<String>,
+    tags: Vec<String>,
+    notes: String,
+    elapsed_seconds: u64,
+    elapsed: String,
+    running: bool,
+    sessions: Vec<ExportSession>,
+}
+
+impl ExportTimer {
+    // interval-only formats (timewarrior, org, ical) have no way to express adjustments
+    fn intervals(&self) -> impl Iterator<Item = &ExportSession> {
+        self.sessions.iter().filter(|s| !s.interval.is_adjustment())
+    }
+
+    // the project first, then the tags, for formats with a single category list
+    fn categories(&self) -> Vec<&str> {
+        self.project
+            .iter()
+            .chain(&self.tags)
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+struct ExportDocument {
+    exported_at: String,
+    timers: Vec<ExportTimer>,
+}
+
+// one line per record for ndjson, tagged so timers and sessions can share a stream
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ExportRecord<'a> {
+    Timer {
+        timer_id: usize,
+        label: &'a str,
+        project: Option<&'a str>,
+        tags: &'a [String],
+        notes: &'a str,
+        elapsed_seconds: u64,
+        elapsed: &'a str,
+        running: bool,
+    },
+    Session {
+        timer_id: usize,
+        label: &'a str,
+        #[serde(flatten)]
+        session: &'a ExportSession,
+    },
+}
+
+pub fn format_timestamp(timestamp: u64) -> String {
+    local_time(timestamp).to_rfc3339()
+}
+
+fn collect(
+    timers: &[PersistedTimer],
+    filter: &Filter,
+    now: u64,
+    time_format: Option<&TimeFormat>,
+) -> Vec<ExportTimer> {
+    timers
+        .iter()
+        .filter(|timer| filter.matches(timer))
+        .map(|timer| {
+            // the timer's own format wins, like on screen
+            let own = timer
+                .time_format
+                .as_deref()
+                .and_then(|format| TimeFormat::parse(format).ok());
+            let format = own.as_ref().or(time_format);
+            ExportTimer {
+                timer_id: timer.timer_id,
+                label: report::timer_label(timer),
+                project: timer.project.clone(),
+                tags: timer.tags.clone(),
+                notes: timer.notes.clone(),
+                elapsed_seconds: timer.elapsed_seconds,
+                elapsed: report::duration_text(timer.elapsed_seconds, format),
+                running: timer.running,
+                sessions: session::with_open(
+                    &timer.sessions,
+                    timer.session_start,
+                    &timer.session_note,
+                    now,
+                )
+                .iter()
+                .filter_map(|s| filter.clip(s).map(|clipped| (s.start, clipped)))
+                .map(|(recorded_start, s)| ExportSession {
+                    start: format_timestamp(s.start),
+                    end: format_timestamp(s.end),
+                    duration_seconds: s.end - s.start,
+                    duration: report::duration_text(s.end - s.start, format),
+                    adjustment_seconds: s.adjustment,
+                    note: s.note.clone(),
+                    interval: s,
+                    recorded_start,
+                })
+                .collect(),
+            }
+        })
+        .collect()
+}
+
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn local_time(timestamp: u64) -> DateTime<Local> {
+    DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .with_timezone(&Local)
+}
+
+// timewarrior splits tags on whitespace unless they're quoted
+fn timew_tag(label: &str) -> String {
+    if label.contains(char::is_whitespace) || label.contains('"') {
+        format!("\"{}\"", label.replace('"', "\\\""))
+    } else {
+        label.to_string()
+    }
+}
+
+// the session note becomes the interval's annotation, on one line
+fn timew_annotation(note: &str) -> String {
+    if note.is_empty() {
+        return String::new();
+    }
+    let note = note.lines().collect::<Vec<_>>().join(" ");
+    format!(" # \"{}\"", note.replace('"', "\\\""))
+}
+
+fn timewarrior_lines(timers: &[ExportTimer]) -> String {
+    let mut lines: Vec<(u64, String)> = timers
+        .iter()
+        .flat_map(|timer| {
+            timer.intervals().map(|session| {
+                let start =
+                    DateTime::from_timestamp(session.interval.start as i64, 0).unwrap_or_default();
+                let end =
+                    DateTime::from_timestamp(session.interval.end as i64, 0).unwrap_or_default();
+                (
+                    session.interval.start,
+                    format!(
+                        "inc {} - {} # {}{}\n",
+                        start.format("%Y%m%dT%H%M%SZ"),
+                        end.format("%Y%m%dT%H%M%SZ"),
+                        std::iter::once(timer.label.as_str())
+                            .chain(timer.categories())
+                            .map(timew_tag)
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                        timew_annotation(&session.note)
+                    ),
+                )
+            })
+        })
+        .collect();
+    // timewarrior expects its data files in chronological order
+    lines.sort_by_key(|(start, _)| *start);
+    lines.into_iter().map(|(_, line)| line).collect()
+}
+
+fn org_timestamp(timestamp: u64) -> String {
+    local_time(timestamp)
+        .format("[%Y-%m-%d %a %H:%M]")
+        .to_string()
+}
+
+// org tags are limited to letters, digits, _, @, # and %
+fn org_tag(tag: &str) -> String {
+    tag.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "_@#%".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+// everything that goes under one org heading
+#[derive(Default)]
+struct OrgHeading<'a> {
+    clocks: Vec<&'a Session>,
+    tags: Vec<String>,
+    notes: Vec<&'a str>,
+}
+
+fn org_document(timers: &[ExportTimer]) -> String {
+    // timers sharing a label end up under the same heading
+    let mut by_label: BTreeMap<&str, OrgHeading> = BTreeMap::new();
+    for timer in timers {
+        let heading = by_label.entry(timer.label.as_str()).or_default();
+        heading
+            .clocks
+            .extend(timer.intervals().map(|s| &s.interval));
+        if !timer.notes.is_empty() {
+            heading.notes.push(&timer.notes);
+        }
+        for tag in timer.categories().into_iter().map(org_tag) {
+            if !heading.tags.contains(&tag) {
+                heading.tags.push(tag);
+            }
+        }
+    }
+    let mut out = String::new();
+    for (label, heading) in by_label {
+        let OrgHeading {
+            mut clocks,
+            tags,
+            notes,
+        } = heading;
+        if clocks.is_empty() {
+            continue;
+        }
+        // org lists the most recent clock first
+        clocks.sort_by_key(|s| std::cmp::Reverse(s.start));
+        if tags.is_empty() {
+            out.push_str(&format!("* {}\n  :LOGBOOK:\n", label));
+        } else {
+            out.push_str(&format!("* {} :{}:\n  :LOGBOOK:\n", label, tags.join(":")));
+        }
+        for clock in &clocks {
+            let minutes = (clock.end - clock.start) / 60;
+            out.push_str(&format!(
+                "  CLOCK: {}--{} => {:>2}:{:02}\n",
+                org_timestamp(clock.start),
+                org_timestamp(clock.end),
+                minutes / 60,
+                minutes % 60
+            ));
+        }
+        out.push_str("  :END:\n");
+        for line in notes.iter().flat_map(|note| note.lines()) {
+            out.push_str(format!("  {}", line).trim_end());
+            out.push('\n');
+        }
+        // session notes as a list, most recent first like the clocks
+        for clock in clocks.iter().filter(|clock| !clock.note.is_empty()) {
+            out.push_str(&format!(
+                "  - {} {}\n",
+                org_timestamp(clock.start),
+                clock.note.replace('\n', "\n    ")
+            ));
+        }
+    }
+    out
+}
+
+fn ical_time(timestamp: u64) -> String {
+    DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+// rfc5545 TEXT escaping
+fn ical_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+// content lines longer than 75 octets get folded onto continuation lines
+fn ical_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn ical_document(timers: &[ExportTimer], now: u64) -> String {
+    let mut out = String::new();
+    ical_line(&mut out, "BEGIN:VCALENDAR");
+    ical_line(&mut out, "VERSION:2.0");
+    ical_line(&mut out, "PRODID:-//stopwatch//tracked sessions//EN");
+    for timer in timers {
+        for session in timer.intervals() {
+            let start = session.interval.start;
+            ical_line(&mut out, "BEGIN:VEVENT");
+            // stable across exports so calendar apps update instead of duplicating,
+            // whatever --from cut off the start
+            ical_line(
+                &mut out,
+                &format!(
+                    "UID:{}-{}@stopwatch",
+                    timer.timer_id, session.recorded_start
+                ),
+            );
+            ical_line(&mut out, &format!("DTSTAMP:{}", ical_time(now)));
+            ical_line(&mut out, &format!("DTSTART:{}", ical_time(start)));
+            ical_line(
+                &mut out,
+                &format!("DTEND:{}", ical_time(session.interval.end)),
+            );
+            ical_line(&mut out, &format!("SUMMARY:{}", ical_text(&timer.label)));
+            let description: Vec<&str> = [session.note.as_str(), timer.notes.as_str()]
+                .into_iter()
+                .filter(|note| !note.is_empty())
+                .collect();
+            if !description.is_empty() {
+                ical_line(
+                    &mut out,
+                    &format!("DESCRIPTION:{}", ical_text(&description.join("\n\n"))),
+                );
+            }
+            let categories = timer.categories();
+            if !categories.is_empty() {
+                let categories: Vec<String> = categories.into_iter().map(ical_text).collect();
+                ical_line(&mut out, &format!("CATEGORIES:{}", categories.join(",")));
+            }
+            ical_line(&mut out, "TRANSP:TRANSPARENT");
+            ical_line(&mut out, "END:VEVENT");
+        }
+    }
+    ical_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+pub fn export(
+    timers: &[PersistedTimer],
+    format: Format,
+    filter: &Filter,
+    now: u64,
+    time_format: Option<&TimeFormat>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let timers = collect(timers, filter, now, time_format);
+    let mut out = String::new();
+    match format {
+        Format::Json => {
+            let document = ExportDocument {
+                exported_at: format_timestamp(now),
+                timers,
+            };
+            out = serde_json::to_string_pretty(&document)?;
+            out.push('\n');
+        }
+        Format::Ndjson => {
+            for timer in &timers {
+                let record = ExportRecord::Timer {
+                    timer_id: timer.timer_id,
+                    label: &timer.label,
+                    project: timer.project.as_deref(),
+                    tags: &timer.tags,
+                    notes: &timer.notes,
+                    elapsed_seconds: timer.elapsed_seconds,
+                    elapsed: &timer.elapsed,
+                    running: timer.running,
+                };
+                out.push_str(&serde_json::to_string(&record)?);
+                out.push('\n');
+                for session in &timer.sessions {
+                    let record = ExportRecord::Session {
+                        timer_id: timer.timer_id,
+                        label: &timer.label,
+                        session,
+                    };
+                    out.push_str(&serde_json::to_string(&record)?);
+                    out.push('\n');
+                }
+            }
+        }
+        Format::Csv => {
+            out.push_str(
+                "timer_id,label,project,tags,start,end,duration_seconds,adjustment_seconds,note,timer_notes,duration\n",
+            );
+            for timer in &timers {
+                for session in &timer.sessions {
+                    out.push_str(&format!(
+                        "{},{},{},{},{},{},{},{},{},{},{}\n",
+                        timer.timer_id,
+                        csv_field(&timer.label),
+                        csv_field(timer.project.as_deref().unwrap_or_default()),
+                        csv_field(&timer.tags.join(" ")),
+                        session.start,
+                        session.end,
+                        session.duration_seconds,
+                        session.adjustment_seconds,
+                        csv_field(&session.note),
+                        csv_field(&timer.notes),
+                        csv_field(&session.duration)
+                    ));
+                }
+            }
+        }
+        Format::Timewarrior => out = timewarrior_lines(&timers),
+        Format::Org => out = org_document(&timers),
+        Format::Ical => out = ical_document(&timers, now),
+    }
+    Ok(out)
+}
+
+// lets users type ~/somewhere in paths
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    const NOW: u64 = 1709294400; // 2024-03-01 12:00 UTC
+
+    // two timers on 2024-03-01 UTC: code review 09:00-10:00 and 11:00-11:30,
+    // email 10:00-10:30 plus a correction
+    const TIMERS: &str = r#"
+        [[timers]]
+        timer_id = 1
+        elapsed_seconds = 5400
+        last_wall_clock = 0
+        label = "code review"
+        running = false
+        sessions = [
+            { start = 1709283600, end = 1709287200 },
+            { start = 1709290800, end = 1709292600 },
+        ]
+
+        [[timers]]
+        timer_id = 2
+        elapsed_seconds = 2400
+        last_wall_clock = 0
+        label = "email"
+        running = false
+        sessions = [
+            { start = 1709287200, end = 1709289000 },
+            { start = 1709289000, end = 1709289000, adjustment = 600 },
+        ]
+    "#;
+
+    fn timers(toml: &str) -> Vec<PersistedTimer> {
+        #[derive(Deserialize)]
+        struct Timers {
+            timers: Vec<PersistedTimer>,
+        }
+        toml::from_str::<Timers>(toml).unwrap().timers
+    }
+
+    fn exported(format: Format, filter: &Filter) -> String {
+        export(&timers(TIMERS), format, filter, NOW, None).unwrap()
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn timew_tags_are_quoted_when_needed() {
+        assert_eq!(timew_tag("email"), "email");
+        assert_eq!(timew_tag("code review"), "\"code review\"");
+        assert_eq!(timew_tag("say\"hi"), "\"say\\\"hi\"");
+    }
+
+    #[test]
+    fn timew_annotations_are_one_quoted_line() {
+        assert_eq!(timew_annotation(""), "");
+        assert_eq!(timew_annotation("fixed it"), " # \"fixed it\"");
+        assert_eq!(
+            timew_annotation("first\nsecond \"quoted\""),
+            " # \"first second \\\"quoted\\\"\""
+        );
+    }
+
+    #[test]
+    fn timewarrior_lines_in_order() {
+        assert_eq!(
+            exported(Format::Timewarrior, &Filter::default()),
+            "inc 20240301T090000Z - 20240301T100000Z # \"code review\"\n\
+             inc 20240301T100000Z - 20240301T103000Z # email\n\
+             inc 20240301T110000Z - 20240301T113000Z # \"code review\"\n"
+        );
+    }
+
+    #[test]
+    fn org_clocks_most_recent_first() {
+        let clock = |start: u64, end: u64, length: &str| {
+            format!(
+                "  CLOCK: {}--{} => {}\n",
+                org_timestamp(start),
+                org_timestamp(end),
+                length
+            )
+        };
+        let expected = [
+            "* code review\n  :LOGBOOK:\n".to_string(),
+            clock(1709290800, 1709292600, " 0:30"),
+            clock(1709283600, 1709287200, " 1:00"),
+            "  :END:\n* email\n  :LOGBOOK:\n".to_string(),
+            clock(1709287200, 1709289000, " 0:30"),
+            "  :END:\n".to_string(),
+        ]
+        .concat();
+        assert_eq!(exported(Format::Org, &Filter::default()), expected);
+    }
+
+    #[test]
+    fn ical_text_is_escaped() {
+        assert_eq!(ical_text("a,b;c\\d\r\ne"), r"a\,b\;c\\d\ne");
+        assert_eq!(ical_text("plain"), "plain");
+    }
+
+    #[test]
+    fn ical_lines_fold_at_75_octets() {
+        let mut out = String::new();
+        ical_line(&mut out, &"x".repeat(80));
+        assert_eq!(out, format!("{}\r\n {}\r\n", "x".repeat(75), "x".repeat(5)));
+
+        // é is two octets, the fold goes before it rather than through it
+        let mut out = String::new();
+        ical_line(&mut out, &format!("a{}", "é".repeat(37)));
+        assert_eq!(out, format!("a{}\r\n", "é".repeat(37)));
+        let mut out = String::new();
+        ical_line(&mut out, &format!("aa{}", "é".repeat(37)));
+        assert_eq!(out, format!("aa{}\r\n é\r\n", "é".repeat(36)));
+    }
+
+    #[test]
+    fn ical_uid_keeps_the_recorded_start() {
+        // --from cuts the first session at 09:30, its UID stays on 09:00
+        let filter = Filter {
+            from: Some(1709285400),
+            to: Some(1709287200),
+            ..Filter::default()
+        };
+        assert_eq!(
+            exported(Format::Ical, &filter),
+            [
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                "PRODID:-//stopwatch//tracked sessions//EN",
+                "BEGIN:VEVENT",
+                "UID:1-1709283600@stopwatch",
+                "DTSTAMP:20240301T120000Z",
+                "DTSTART:20240301T093000Z",
+                "DTEND:20240301T100000Z",
+                "SUMMARY:code review",
+                "TRANSP:TRANSPARENT",
+                "END:VEVENT",
+                "END:VCALENDAR",
+                "",
+            ]
+            .join("\r\n")
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Padding},
+    Frame,
+};
+
+use crate::fuzzy;
+use crate::input::LineInput;
+
+// one timer as the finder shows it
+pub struct Candidate {
+    pub index: usize,   // into the grid
+    pub title: String,  // "3  incident-4412"
+    pub search: String, // everything a query can hit, label, #tags, @project, number and id
+    pub detail: String, // time and state, for the preview column
+    pub color: Option<Color>,
+}
+
+pub enum FinderResult {
+    Select(usize),
+    Close,
+    Open,
+}
+
+// `/`, type part of a label, tag or number and enter jumps to the timer
+pub struct Finder {
+    pub query: LineInput,
+    selected: usize,
+}
+
+impl Finder {
+    pub fn new() -> Self {
+        Self {
+            query: LineInput::new(64),
+            selected: 0,
+        }
+    }
+
+    // best match first, the grid order when nothing is typed
+    fn matches<'a>(&self, candidates: &'a [Candidate]) -> Vec<&'a Candidate> {
+        let query = self.query.text();
+        let mut scored: Vec<(i64, &Candidate)> = candidates
+            .iter()
+            .filter_map(|c| fuzzy::score(query, &c.search).map(|score| (score, c)))
+            .collect();
+        if !query.is_empty() {
+            scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        }
+        scored.into_iter().map(|(_, c)| c).collect()
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent, candidates: &[Candidate]) -> FinderResult {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return FinderResult::Close,
+            KeyCode::Enter => {
+                return match self.matches(candidates).get(self.selected) {
+                    Some(candidate) => FinderResult::Select(candidate.index),
+                    None => FinderResult::Open,
+                }
+            }
+            KeyCode::Up | KeyCode::BackTab => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('p') if ctrl => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Tab => self.selected += 1,
+            KeyCode::Char('n') if ctrl => self.selected += 1,
+            _ => {
+                if self.query.handle_key(key) {
+                    self.selected = 0;
+                }
+            }
+        }
+        self.selected = self
+            .selected
+            .min(self.matches(candidates).len().saturating_sub(1));
+        FinderResult::Open
+    }
+
+    pub fn draw(&self, frame: &mut Frame, candidates: &[Candidate]) {
+        let matches = self.matches(candidates);
+        let area = frame.area();
+        let width = (area.width / 2).max(50).min(area.width);
+        let height = (candidates.len() as u16 + 4)
+            .clamp(6, (area.height * 2 / 3).max(6))
+            .min(area.height);
+        let finder_area = Rect::new((area.width - width) / 2, area.height / 6, width, height);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Green))
+            .title(format!(
+                " Find timer ({}/{}) ",
+                matches.len(),
+                candidates.len()
+            ))
+            .title_bottom(Line::from(" enter select · esc close ").right_aligned())
+            .padding(Padding::horizontal(1))
+            .style(Style::default().fg(Color::Gray).bg(Color::Black));
+        let inner = block.inner(finder_area);
+        frame.render_widget(Clear, finder_area);
+        frame.render_widget(block, finder_area);
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Min(0),
+            ])
+            .split(inner);
+        self.query.draw(frame, rows[0], "/", Alignment::Left);
+
+        let items: Vec<ListItem> = matches
+            .iter()
+            .map(|candidate| {
+                let pad = (rows[2].width as usize).saturating_sub(
+                    candidate.title.chars().count() + candidate.detail.chars().count() + 1,
+                );
+                let text = format!(
+                    "{}{} {}",
+                    candidate.title,
+                    " ".repeat(pad),
+                    candidate.detail
+                );
+                let style = candidate
+                    .color
+                    .map(|color| Style::default().fg(color))
+                    .unwrap_or_default();
+                ListItem::new(text).style(style)
+            })
+            .collect();
+        let empty = items.is_empty();
+        let list = List::new(if empty {
+            vec![ListItem::new("no timer matches")]
+        } else {
+            items
+        })
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut list_state = ListState::default();
+        list_state.select((!empty).then_some(self.selected));
+        frame.render_stateful_widget(list, rows[2], &mut list_state);
+    }
+}
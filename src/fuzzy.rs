@@ -0,0 +1,36 @@
+// subsequence match like most fuzzy finders, "adt" finds "add-timer";
+// None when the query doesn't fit, otherwise higher is better
+pub fn score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous_match: Option<usize> = None;
+    let mut previous_char = ' ';
+    for (i, c) in candidate.chars().enumerate() {
+        if next == query.len() {
+            break;
+        }
+        if c.to_lowercase().eq(query[next].to_lowercase()) {
+            score += 1;
+            // runs of matching characters and matches at word starts count the most
+            if previous_match.is_some_and(|p| p + 1 == i) {
+                score += 5;
+            }
+            if !previous_char.is_alphanumeric() {
+                score += 3;
+            }
+            if i == 0 {
+                score += 2;
+            }
+            previous_match = Some(i);
+            next += 1;
+        }
+        previous_char = c;
+    }
+    // shorter candidates win among equal matches
+    (next == query.len()).then(|| score * 100 - candidate.chars().count() as i64)
+}
@@ -0,0 +1,365 @@
+use chrono::{Local, NaiveDateTime};
+use serde::Deserialize;
+
+use crate::session::Session;
+use crate::tags::parse_tags;
+use crate::template::Mode;
+use crate::{PersistedState, PersistedTimer, Time};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Source {
+    Timewarrior,
+    Toggl,
+}
+
+impl Source {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "timewarrior" | "timew" => Some(Self::Timewarrior),
+            "toggl" => Some(Self::Toggl),
+            _ => None,
+        }
+    }
+
+    // `timew export` gives json, toggl's detailed report is csv
+    pub fn from_path(path: &str) -> Option<Self> {
+        match path.rsplit_once('.')?.1.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Timewarrior),
+            "csv" => Some(Self::Toggl),
+            _ => None,
+        }
+    }
+}
+
+// one finished interval from another tool, already mapped to a label and tags
+pub struct Entry {
+    pub label: String,
+    pub tags: Vec<String>,
+    pub session: Session,
+}
+
+#[derive(Default, Debug)]
+pub struct Summary {
+    pub imported: usize,
+    pub duplicates: usize,
+    pub skipped: usize, // still running or unparseable
+    pub new_timers: usize,
+}
+
+#[derive(Deserialize)]
+struct TimewInterval {
+    start: String,
+    end: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+fn parse_timew_time(value: &str) -> Option<u64> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+        .ok()
+        .map(|dt| dt.and_utc().timestamp().max(0) as u64)
+}
+
+pub fn parse_timewarrior(
+    contents: &str,
+    summary: &mut Summary,
+) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+    let intervals: Vec<TimewInterval> = serde_json::from_str(contents)?;
+    let mut entries = Vec::new();
+    for interval in intervals {
+        let start = parse_timew_time(&interval.start);
+        let end = interval.end.as_deref().and_then(parse_timew_time);
+        // the first tag names the timer like our own export writes it, the rest stay tags
+        let mut tags = interval.tags.into_iter();
+        let label = tags.next().unwrap_or_else(|| "untagged".to_string());
+        match (start, end) {
+            (Some(start), Some(end)) if end > start => entries.push(Entry {
+                label,
+                tags: tags.collect(),
+                session: Session::new(start, end),
+            }),
+            _ => summary.skipped += 1,
+        }
+    }
+    Ok(entries)
+}
+
+// minimal rfc4180 reader, handles quoted commas, doubled quotes and line breaks in quotes
+pub fn parse_csv(contents: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = contents.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => in_quotes = !in_quotes,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+fn parse_local_time(date: &str, time: &str) -> Option<u64> {
+    NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M:%S")
+        .ok()?
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|dt| dt.timestamp().max(0) as u64)
+}
+
+pub fn parse_toggl(
+    contents: &str,
+    summary: &mut Summary,
+) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+    let mut rows = parse_csv(contents).into_iter();
+    let header = rows.next().ok_or("empty toggl export")?;
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("toggl export is missing the '{}' column", name))
+    };
+    let project = column("Project")?;
+    let description = column("Description")?;
+    let start_date = column("Start date")?;
+    let start_time = column("Start time")?;
+    let end_date = column("End date")?;
+    let end_time = column("End time")?;
+    let tags = column("Tags").ok();
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let cell = |i: usize| row.get(i).map(|v| v.trim()).unwrap_or("");
+        if row.iter().all(|v| v.trim().is_empty()) {
+            continue;
+        }
+        let start = parse_local_time(cell(start_date), cell(start_time));
+        let end = parse_local_time(cell(end_date), cell(end_time));
+        // the project is the closest thing to a timer, fall back to the description
+        let label = [cell(project), cell(description)]
+            .into_iter()
+            .find(|v| !v.is_empty())
+            .unwrap_or("no project")
+            .to_string();
+        match (start, end) {
+            (Some(start), Some(end)) if end > start => entries.push(Entry {
+                label,
+                tags: tags.map(|i| parse_tags(cell(i))).unwrap_or_default(),
+                session: Session::new(start, end),
+            }),
+            _ => summary.skipped += 1,
+        }
+    }
+    Ok(entries)
+}
+
+// add entries to the timer with the same label, archived ones included so old history lands
+// where it belongs, or to a new paused timer, and give it their tags
+pub fn merge(state: &mut PersistedState, entries: Vec<Entry>, summary: &mut Summary) {
+    let now = Time::current_unix_time();
+    let mut next_timer_id = state.next_timer_id();
+    let first_new_id = next_timer_id;
+    for entry in entries {
+        // indexes past the grid are in the archive
+        let idx = match state
+            .timers
+            .iter()
+            .chain(&state.archive)
+            .position(|t| t.label.as_deref() == Some(entry.label.as_str()))
+        {
+            Some(idx) => idx,
+            None => {
+                state.timers.push(PersistedTimer {
+                    timer_id: next_timer_id,
+                    elapsed_seconds: 0,
+                    last_wall_clock: now,
+                    label: Some(entry.label.clone()),
+                    running: false,
+                    session_start: None,
+                    sessions: Vec::new(),
+                    mode: Mode::default(),
+                    target: None,
+                    color: None,
+                    tags: Vec::new(),
+                    project: None,
+                    notes: String::new(),
+                    session_note: String::new(),
+                    time_format: None,
+                });
+                next_timer_id += 1;
+                summary.new_timers += 1;
+                state.timers.len() - 1
+            }
+        };
+        let timer = match idx.checked_sub(state.timers.len()) {
+            Some(archived) => &mut state.archive[archived],
+            None => &mut state.timers[idx],
+        };
+        for tag in entry.tags {
+            if !timer.tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+                timer.tags.push(tag);
+            }
+        }
+        if timer
+            .sessions
+            .iter()
+            .any(|session| session.same_interval(&entry.session))
+        {
+            summary.duplicates += 1;
+            continue;
+        }
+        // timers created by the import show the imported total
+        if timer.timer_id >= first_new_id {
+            timer.elapsed_seconds += entry.session.end - entry.session.start;
+        }
+        timer.sessions.push(entry.session);
+        summary.imported += 1;
+    }
+    for timer in state.timers.iter_mut().chain(&mut state.archive) {
+        timer.sessions.sort_by_key(|s| s.start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_source() {
+        assert_eq!(Source::parse("timew"), Some(Source::Timewarrior));
+        assert_eq!(Source::parse("Toggl"), Some(Source::Toggl));
+        assert_eq!(Source::parse("harvest"), None);
+        assert_eq!(
+            Source::from_path("~/export.JSON"),
+            Some(Source::Timewarrior)
+        );
+        assert_eq!(Source::from_path("report.csv"), Some(Source::Toggl));
+        assert_eq!(Source::from_path("report"), None);
+    }
+
+    #[test]
+    fn reads_quoted_csv() {
+        let rows = parse_csv("\u{feff}a,\"b, c\",\"say \"\"hi\"\"\"\r\n\"two\nlines\",,x");
+        assert_eq!(
+            rows,
+            vec![vec!["a", "b, c", "say \"hi\""], vec!["two\nlines", "", "x"],]
+        );
+    }
+
+    #[test]
+    fn timewarrior_tags_become_label_and_tags() {
+        let mut summary = Summary::default();
+        let entries = parse_timewarrior(
+            r#"[
+                {"start": "20240301T090000Z", "end": "20240301T100000Z", "tags": ["acme", "review", "call"]},
+                {"start": "20240301T110000Z", "end": "20240301T113000Z"},
+                {"start": "20240301T120000Z"},
+                {"start": "garbage", "end": "20240301T130000Z"}
+            ]"#,
+            &mut summary,
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].label, "acme");
+        assert_eq!(entries[0].tags, vec!["review", "call"]);
+        assert_eq!(entries[0].session.start, 1709283600);
+        assert_eq!(entries[0].session.end - entries[0].session.start, 3600);
+        assert_eq!(entries[1].label, "untagged");
+        assert!(entries[1].tags.is_empty());
+        assert_eq!(summary.skipped, 2);
+    }
+
+    #[test]
+    fn toggl_rows_use_project_then_description() {
+        let mut summary = Summary::default();
+        let csv = "Project,Description,Start date,Start time,End date,End time,Tags\n\
+                   Acme,calls,2024-03-01,09:00:00,2024-03-01,09:45:00,\"client, review\"\n\
+                   ,standup,2024-03-01,10:00:00,2024-03-01,10:15:00,\n\
+                   ,,2024-03-01,11:00:00,2024-03-01,10:00:00,\n\
+                   \n";
+        let entries = parse_toggl(csv, &mut summary).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].label, "Acme");
+        assert_eq!(entries[0].tags, vec!["client", "review"]);
+        assert_eq!(entries[0].session.end - entries[0].session.start, 2700);
+        assert_eq!(entries[1].label, "standup");
+        assert_eq!(summary.skipped, 1);
+        assert!(parse_toggl("Project,Description\n", &mut summary).is_err());
+    }
+
+    #[test]
+    fn merge_skips_duplicates_and_adds_tags() {
+        let mut state = PersistedState::empty(0);
+        let entry = |label: &str, tags: &[&str], start| Entry {
+            label: label.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            session: Session::new(start, start + 600),
+        };
+        let mut summary = Summary::default();
+        merge(
+            &mut state,
+            vec![
+                entry("acme", &["review"], 100),
+                entry("acme", &["Review", "call"], 2000),
+            ],
+            &mut summary,
+        );
+        merge(&mut state, vec![entry("acme", &[], 100)], &mut summary);
+        assert_eq!(state.timers.len(), 1);
+        let timer = &state.timers[0];
+        assert_eq!(timer.tags, vec!["review", "call"]);
+        assert_eq!(timer.sessions.len(), 2);
+        assert_eq!(timer.elapsed_seconds, 1200);
+        assert_eq!(
+            (summary.imported, summary.duplicates, summary.new_timers),
+            (2, 1, 1)
+        );
+    }
+
+    #[test]
+    fn merge_finds_archived_timers() {
+        let mut state = PersistedState::empty(0);
+        let mut summary = Summary::default();
+        merge(
+            &mut state,
+            vec![Entry {
+                label: "old project".to_string(),
+                tags: Vec::new(),
+                session: Session::new(100, 700),
+            }],
+            &mut summary,
+        );
+        state.archive.push(state.timers.remove(0));
+        merge(
+            &mut state,
+            vec![Entry {
+                label: "old project".to_string(),
+                tags: vec!["2023".to_string()],
+                session: Session::new(50, 60),
+            }],
+            &mut summary,
+        );
+        assert!(state.timers.is_empty());
+        assert_eq!(state.archive[0].tags, vec!["2023"]);
+        assert_eq!(
+            state.archive[0].sessions,
+            vec![Session::new(50, 60), Session::new(100, 700)]
+        );
+        assert_eq!(summary.new_timers, 1);
+    }
+}
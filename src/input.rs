@@ -0,0 +1,301 @@
+use crossterm::event::{
+    DisableBracketedPaste, EnableBracketedPaste, KeyCode, KeyEvent, KeyModifiers,
+};
+use crossterm::execute;
+use ratatui::{
+    layout::{Alignment, Position, Rect},
+    widgets::Paragraph,
+    Frame,
+};
+use std::io::stdout;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// pasted text arrives as one Event::Paste instead of a key per character
+pub fn enable_paste() -> Result<(), Box<dyn std::error::Error>> {
+    execute!(stdout(), EnableBracketedPaste)?;
+    Ok(())
+}
+
+pub fn disable_paste() -> Result<(), Box<dyn std::error::Error>> {
+    execute!(stdout(), DisableBracketedPaste)?;
+    Ok(())
+}
+
+// single line text field with a real cursor, readline keys and a length limit in graphemes
+#[derive(Default)]
+pub struct LineInput {
+    text: String,
+    cursor: usize, // byte offset, always on a grapheme boundary
+    max_len: usize,
+}
+
+impl LineInput {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            text: String::new(),
+            cursor: 0,
+            max_len,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // replaces the text, cut to the limit, with the cursor at the end
+    pub fn set(&mut self, text: &str) {
+        self.text.clear();
+        self.cursor = 0;
+        self.insert(text);
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    // newlines and tabs become spaces, whatever doesn't fit is dropped
+    pub fn insert(&mut self, text: &str) {
+        let room = self
+            .max_len
+            .saturating_sub(self.text.graphemes(true).count());
+        let cleaned: String = text
+            .trim_end_matches(['\r', '\n'])
+            .graphemes(true)
+            .filter(|g| !g.chars().all(char::is_control) || g.chars().any(char::is_whitespace))
+            .map(|g| {
+                if g.chars().any(char::is_whitespace) {
+                    " "
+                } else {
+                    g
+                }
+            })
+            .take(room)
+            .collect();
+        self.text.insert_str(self.cursor, &cleaned);
+        self.cursor += cleaned.len();
+    }
+
+    fn previous_boundary(&self) -> usize {
+        self.text[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.text[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(self.cursor, |g| self.cursor + g.len())
+    }
+
+    // start of the word before the cursor, readline treats anything but whitespace as word
+    fn previous_word(&self) -> usize {
+        let before = self.text[..self.cursor].trim_end();
+        before
+            .char_indices()
+            .rfind(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8())
+    }
+
+    fn next_word(&self) -> usize {
+        let after = &self.text[self.cursor..];
+        let word_start = after.len() - after.trim_start().len();
+        after[word_start..]
+            .find(char::is_whitespace)
+            .map_or(self.text.len(), |i| self.cursor + word_start + i)
+    }
+
+    // true when the key was for the text field
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.text.len(),
+            KeyCode::Char('b') if ctrl => self.cursor = self.previous_boundary(),
+            KeyCode::Char('f') if ctrl => self.cursor = self.next_boundary(),
+            KeyCode::Char('b') if alt => self.cursor = self.previous_word(),
+            KeyCode::Char('f') if alt => self.cursor = self.next_word(),
+            KeyCode::Char('w') if ctrl => {
+                let start = self.previous_word();
+                self.text.replace_range(start..self.cursor, "");
+                self.cursor = start;
+            }
+            KeyCode::Char('u') if ctrl => {
+                self.text.replace_range(..self.cursor, "");
+                self.cursor = 0;
+            }
+            KeyCode::Char('k') if ctrl => self.text.truncate(self.cursor),
+            KeyCode::Char('h') if ctrl => self.backspace(),
+            KeyCode::Char(_) if ctrl || alt => return false,
+            KeyCode::Char(c) => self.insert(c.encode_utf8(&mut [0; 4])),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => {
+                let end = self.next_boundary();
+                self.text.replace_range(self.cursor..end, "");
+            }
+            KeyCode::Left if ctrl || alt => self.cursor = self.previous_word(),
+            KeyCode::Right if ctrl || alt => self.cursor = self.next_word(),
+            KeyCode::Left => self.cursor = self.previous_boundary(),
+            KeyCode::Right => self.cursor = self.next_boundary(),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn backspace(&mut self) {
+        let start = self.previous_boundary();
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    // the part of the text that fits in width cells with the cursor in it, and the cursor column;
+    // cut off ends are marked with '…'
+    fn visible(&self, width: usize) -> (String, usize) {
+        let graphemes: Vec<(usize, &str)> = self.text.grapheme_indices(true).collect();
+        let cursor = graphemes
+            .iter()
+            .position(|(i, _)| *i >= self.cursor)
+            .unwrap_or(graphemes.len());
+        let cells =
+            |range: &[(usize, &str)]| -> usize { range.iter().map(|(_, g)| g.width()).sum() };
+        // the cursor needs a cell of its own after the text before it
+        let room = width.saturating_sub(1);
+        let mut start = 0;
+        while start < cursor && cells(&graphemes[start..cursor]) + usize::from(start > 0) > room {
+            start += 1;
+        }
+        let mut shown = if start > 0 {
+            "…".to_string()
+        } else {
+            String::new()
+        };
+        let cursor_column = shown.width() + cells(&graphemes[start..cursor]);
+        let mut used = shown.width();
+        for (n, (_, g)) in graphemes.iter().enumerate().skip(start) {
+            let more_after = n + 1 < graphemes.len();
+            if used + g.width() + usize::from(more_after) > width {
+                if used < width {
+                    shown.push('…');
+                }
+                break;
+            }
+            shown.push_str(g);
+            used += g.width();
+        }
+        (shown, cursor_column)
+    }
+
+    // one line "prompt text" in area, with the terminal cursor placed in the text
+    pub fn draw(&self, frame: &mut Frame, area: Rect, prompt: &str, alignment: Alignment) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        let prompt_width = prompt.width().min(area.width as usize);
+        let (shown, cursor_column) = self.visible(area.width as usize - prompt_width);
+        let line = format!("{}{}", prompt, shown);
+        // leave a cell for the cursor so a centered line doesn't jump once it's at the end
+        let line_width = (line.width() + usize::from(cursor_column == shown.width()))
+            .min(area.width as usize) as u16;
+        let x = match alignment {
+            Alignment::Left => area.x,
+            Alignment::Center => area.x + (area.width - line_width) / 2,
+            Alignment::Right => area.x + area.width - line_width,
+        };
+        let line_area = Rect::new(x, area.y, area.right() - x, 1);
+        frame.render_widget(Paragraph::new(line), line_area);
+        frame.set_cursor_position(Position::new(
+            (x + (prompt_width + cursor_column) as u16).min(area.right() - 1),
+            area.y,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut LineInput, code: KeyCode, modifiers: KeyModifiers) {
+        assert!(input.handle_key(KeyEvent::new(code, modifiers)));
+    }
+
+    fn ctrl(input: &mut LineInput, c: char) {
+        press(input, KeyCode::Char(c), KeyModifiers::CONTROL);
+    }
+
+    fn typed(text: &str) -> LineInput {
+        let mut input = LineInput::new(100);
+        input.set(text);
+        input
+    }
+
+    #[test]
+    fn moves_over_whole_graphemes() {
+        // e and a combining accent, a thumbs up with a skin tone
+        let mut input = typed("ae\u{301}👍🏽b");
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(input.text(), "a👍🏽b");
+        press(&mut input, KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(input.text(), "ab");
+        ctrl(&mut input, 'f');
+        press(&mut input, KeyCode::Char('c'), KeyModifiers::NONE);
+        assert_eq!(input.text(), "abc");
+    }
+
+    #[test]
+    fn kills_like_readline() {
+        let mut input = typed("one two three");
+        ctrl(&mut input, 'w');
+        assert_eq!(input.text(), "one two ");
+        ctrl(&mut input, 'w');
+        assert_eq!(input.text(), "one ");
+
+        let mut input = typed("one two three");
+        ctrl(&mut input, 'a');
+        press(&mut input, KeyCode::Char('f'), KeyModifiers::ALT);
+        ctrl(&mut input, 'k');
+        assert_eq!(input.text(), "one");
+
+        let mut input = typed("one two three");
+        press(&mut input, KeyCode::Left, KeyModifiers::CONTROL);
+        ctrl(&mut input, 'u');
+        assert_eq!(input.text(), "three");
+        assert!(!input.handle_key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL)));
+    }
+
+    #[test]
+    fn insert_keeps_to_the_limit() {
+        let mut input = LineInput::new(5);
+        input.insert("he\u{301}llo world");
+        assert_eq!(input.text(), "he\u{301}llo");
+        input.insert("x");
+        assert_eq!(input.text(), "he\u{301}llo");
+        input.set("a\nb\tc\r\n");
+        assert_eq!(input.text(), "a b c");
+        input.set("ab\r\n");
+        ctrl(&mut input, 'a');
+        input.insert("\u{7}xyz");
+        assert_eq!(input.text(), "xyzab");
+    }
+
+    #[test]
+    fn visible_part_follows_the_cursor() {
+        assert_eq!(typed("ab").visible(10), ("ab".to_string(), 2));
+
+        // seven characters two cells wide each
+        let mut input = typed("日本語テキスト");
+        assert_eq!(input.visible(8), ("…キスト".to_string(), 7));
+        ctrl(&mut input, 'a');
+        assert_eq!(input.visible(8), ("日本語…".to_string(), 0));
+        press(&mut input, KeyCode::Right, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Right, KeyModifiers::NONE);
+        assert_eq!(input.visible(8), ("日本語…".to_string(), 4));
+    }
+}
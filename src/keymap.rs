@@ -0,0 +1,536 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fmt;
+
+// everything a key can do on the timer grid, `[keys]` in config.toml uses the kebab-case names
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    Quit,
+    AddTimer,
+    DeleteTimer,
+    ArchiveTimer,
+    BrowseShelf,
+    ResetTimer,
+    Undo,
+    Redo,
+    NextTimer,
+    SelectLeft,
+    SelectRight,
+    SelectUp,
+    SelectDown,
+    MoveLeft,
+    MoveRight,
+    PreviousPage,
+    NextPage,
+    FindTimer,
+    TogglePause,
+    ToggleExclusive,
+    EditLabel,
+    EditTags,
+    EditProject,
+    Filter,
+    TimerNotes,
+    SessionNotes,
+    AdjustTime,
+    ShowReport,
+    Export,
+    CycleView,
+    ToggleHelp,
+    FasterUi,
+    SlowerUi,
+    CommandLine,
+    CommandPalette,
+    DebugLog,
+}
+
+impl Action {
+    // in the order the help overlay lists them
+    pub const ALL: [Action; 36] = [
+        Self::Quit,
+        Self::AddTimer,
+        Self::DeleteTimer,
+        Self::ArchiveTimer,
+        Self::BrowseShelf,
+        Self::ResetTimer,
+        Self::Undo,
+        Self::Redo,
+        Self::NextTimer,
+        Self::SelectLeft,
+        Self::SelectRight,
+        Self::SelectUp,
+        Self::SelectDown,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::PreviousPage,
+        Self::NextPage,
+        Self::FindTimer,
+        Self::TogglePause,
+        Self::ToggleExclusive,
+        Self::EditLabel,
+        Self::EditTags,
+        Self::EditProject,
+        Self::Filter,
+        Self::TimerNotes,
+        Self::SessionNotes,
+        Self::AdjustTime,
+        Self::ShowReport,
+        Self::Export,
+        Self::CycleView,
+        Self::ToggleHelp,
+        Self::FasterUi,
+        Self::SlowerUi,
+        Self::CommandLine,
+        Self::CommandPalette,
+        Self::DebugLog,
+    ];
+
+    // the name used in config.toml, "add-timer"
+    pub fn name(self) -> String {
+        let mut name = String::new();
+        for c in format!("{:?}", self).chars() {
+            if c.is_ascii_uppercase() && !name.is_empty() {
+                name.push('-');
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+        name
+    }
+
+    pub fn description(self) -> String {
+        match self {
+            Self::Quit => "Quit".to_string(),
+            Self::AddTimer => "Add timer".to_string(),
+            Self::DeleteTimer => "Move selected timer to trash".to_string(),
+            Self::ArchiveTimer => "Archive selected timer".to_string(),
+            Self::BrowseShelf => "Browse archive and trash".to_string(),
+            Self::ResetTimer => "Reset selected timer".to_string(),
+            Self::Undo => "Undo".to_string(),
+            Self::Redo => "Redo".to_string(),
+            Self::NextTimer => "Next timer".to_string(),
+            Self::SelectLeft => "Timer to the left".to_string(),
+            Self::SelectRight => "Timer to the right".to_string(),
+            Self::SelectUp => "Timer above".to_string(),
+            Self::SelectDown => "Timer below".to_string(),
+            Self::MoveLeft => "Move timer back".to_string(),
+            Self::MoveRight => "Move timer forward".to_string(),
+            Self::PreviousPage => "Previous page of timers".to_string(),
+            Self::NextPage => "Next page of timers".to_string(),
+            Self::FindTimer => "Find timer by label or tag".to_string(),
+            Self::TogglePause => "Pause/resume selected timer".to_string(),
+            Self::ToggleExclusive => "Toggle exclusive mode".to_string(),
+            Self::EditLabel => "Set label for timer".to_string(),
+            Self::EditTags => "Edit tags".to_string(),
+            Self::EditProject => "Edit project".to_string(),
+            Self::Filter => "Filter by tag or project".to_string(),
+            Self::TimerNotes => "Notes for timer".to_string(),
+            Self::SessionNotes => "Notes for a session".to_string(),
+            Self::AdjustTime => "Adjust time of timer".to_string(),
+            Self::ShowReport => "Show time report".to_string(),
+            Self::Export => "Export timers to file".to_string(),
+            Self::CycleView => "Switch between grid, table, focus and sidebar".to_string(),
+            Self::ToggleHelp => "Toggle help".to_string(),
+            Self::FasterUi => "Increase UI FPS".to_string(),
+            Self::SlowerUi => "Decrease UI FPS".to_string(),
+            Self::CommandLine => "Type a command".to_string(),
+            Self::CommandPalette => "Command palette".to_string(),
+            Self::DebugLog => "Show debug log".to_string(),
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Self::Quit => &["ctrl+q"],
+            Self::AddTimer => &["ctrl+a"],
+            Self::DeleteTimer => &["ctrl+d"],
+            Self::ArchiveTimer => &["A"],
+            Self::BrowseShelf => &["b"],
+            Self::ResetTimer => &["ctrl+r"],
+            Self::Undo => &["u"],
+            Self::Redo => &["ctrl+y"],
+            Self::NextTimer => &["tab"],
+            Self::SelectLeft => &["left", "h"],
+            Self::SelectRight => &["right", "l"],
+            Self::SelectUp => &["up", "k"],
+            Self::SelectDown => &["down", "j"],
+            Self::MoveLeft => &["shift+left", "H"],
+            Self::MoveRight => &["shift+right", "L"],
+            Self::PreviousPage => &["pageup"],
+            Self::NextPage => &["pagedown"],
+            Self::FindTimer => &["/"],
+            Self::TogglePause => &["space"],
+            Self::ToggleExclusive => &["e"],
+            Self::EditLabel => &["r"],
+            Self::EditTags => &["#"],
+            Self::EditProject => &["p"],
+            Self::Filter => &["f"],
+            Self::TimerNotes => &["n"],
+            Self::SessionNotes => &["N"],
+            Self::AdjustTime => &["t"],
+            Self::ShowReport => &["s"],
+            Self::Export => &["x"],
+            Self::CycleView => &["v"],
+            Self::ToggleHelp => &["?"],
+            Self::FasterUi => &["+", "="],
+            Self::SlowerUi => &["-"],
+            Self::CommandLine => &[":"],
+            Self::CommandPalette => &["ctrl+k"],
+            Self::DebugLog => &["ctrl+p"],
+        }
+    }
+}
+
+// a key with its modifiers, shift is folded into the character for printable keys
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        // terminals disagree on whether 'A' or '#' come with shift, the character already says it
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    pub fn from_event(key: KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+
+    // "ctrl+a", "A", "shift+tab", "alt+left", "space", "f5", "#"
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let (mods, key) = match input.rsplit_once('+') {
+            // a lone "+" or "ctrl++" is the plus key
+            Some((mods, "")) => (mods.strip_suffix('+').unwrap_or(mods), "+"),
+            Some(split) => split,
+            None => ("", input),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in mods.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{}' in '{}'", modifier, input)),
+            };
+        }
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (Some(c), None) if modifiers.contains(KeyModifiers::CONTROL) => {
+                KeyCode::Char(c.to_ascii_lowercase())
+            }
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                other => match other.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) if (1..=24).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{}'", input)),
+                },
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+// same shape the help overlay always used, "ctrl + a"
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl + ")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt + ")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift + ")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "shift + tab"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "del"),
+            KeyCode::Insert => write!(f, "ins"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pgup"),
+            KeyCode::PageDown => write!(f, "pgdn"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+// `undo = "u"` or `undo = ["u", "ctrl+z"]`, an empty list unbinds the action
+#[derive(Debug, Clone)]
+pub struct Binding(Vec<KeyChord>);
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Keys {
+            One(String),
+            Many(Vec<String>),
+        }
+        let keys = match Keys::deserialize(deserializer)? {
+            Keys::One(key) => vec![key],
+            Keys::Many(keys) => keys,
+        };
+        keys.iter()
+            .map(|key| KeyChord::parse(key))
+            .collect::<Result<_, _>>()
+            .map(Binding)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+pub struct Keymap {
+    bindings: Vec<(KeyChord, Action)>,
+}
+
+impl Keymap {
+    // defaults with the config overrides applied, plus a line for every entry that was skipped
+    // and every clash that was found; a key set in the config beats a default, between two
+    // config keys the first action wins
+    pub fn build(keys: &BTreeMap<String, toml::Value>) -> (Self, Vec<String>) {
+        let mut conflicts = Vec::new();
+        // one typo only loses its own entry
+        let mut overrides = BTreeMap::new();
+        for (name, value) in keys {
+            let action = Action::ALL
+                .into_iter()
+                .find(|action| action.name() == *name);
+            match (action, Binding::deserialize(value.clone())) {
+                (Some(action), Ok(binding)) => {
+                    overrides.insert(action, binding);
+                }
+                (None, _) => conflicts.push(format!("unknown action '{}', skipped", name)),
+                (Some(_), Err(e)) => conflicts.push(format!("{}: {}, skipped", name, e.message())),
+            }
+        }
+        let mut bindings: Vec<(KeyChord, Action)> = Vec::new();
+        for action in Action::ALL {
+            let Some(Binding(keys)) = overrides.get(&action) else {
+                continue;
+            };
+            for key in keys {
+                match bindings.iter().find(|(bound, _)| bound == key) {
+                    Some((_, other)) => conflicts.push(format!(
+                        "{} is bound to both {} and {}, keeping {}",
+                        key,
+                        other.name(),
+                        action.name(),
+                        other.name()
+                    )),
+                    None => bindings.push((*key, action)),
+                }
+            }
+        }
+        for action in Action::ALL {
+            if overrides.contains_key(&action) {
+                continue;
+            }
+            for key in action.default_keys() {
+                let key = KeyChord::parse(key).expect("default key bindings parse");
+                match bindings.iter().find(|(bound, _)| *bound == key) {
+                    Some((_, other)) => conflicts.push(format!(
+                        "{} is now {}, {} lost its default key",
+                        key,
+                        other.name(),
+                        action.name()
+                    )),
+                    None => bindings.push((key, action)),
+                }
+            }
+        }
+        (Self { bindings }, conflicts)
+    }
+
+    pub fn action(&self, key: KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from_event(key);
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == chord)
+            .map(|(_, action)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<KeyChord> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    // " ctrl + a - Add timer" for every bound action, in Action::ALL order
+    pub fn help_lines(&self) -> Vec<String> {
+        Action::ALL
+            .iter()
+            .filter_map(|action| {
+                let keys = self.keys(*action);
+                let keys: Vec<String> = keys.iter().map(KeyChord::to_string).collect();
+                (!keys.is_empty())
+                    .then(|| format!(" {} - {}", keys.join(" / "), action.description()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn keys(entries: &str) -> BTreeMap<String, toml::Value> {
+        toml::from_str(entries).unwrap()
+    }
+
+    #[test]
+    fn parses_chords() {
+        let chord = |code, modifiers| KeyChord::new(code, modifiers);
+        assert_eq!(
+            KeyChord::parse("ctrl+A"),
+            Ok(chord(KeyCode::Char('a'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            KeyChord::parse("shift+h"),
+            Ok(chord(KeyCode::Char('H'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            KeyChord::parse("shift+tab"),
+            Ok(chord(KeyCode::BackTab, KeyModifiers::NONE))
+        );
+        assert_eq!(
+            KeyChord::parse("alt+left"),
+            Ok(chord(KeyCode::Left, KeyModifiers::ALT))
+        );
+        assert_eq!(
+            KeyChord::parse("ctrl++"),
+            Ok(chord(KeyCode::Char('+'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            KeyChord::parse("+"),
+            Ok(chord(KeyCode::Char('+'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            KeyChord::parse("F5"),
+            Ok(chord(KeyCode::F(5), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            KeyChord::parse("space"),
+            Ok(chord(KeyCode::Char(' '), KeyModifiers::NONE))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_chords() {
+        assert!(KeyChord::parse("hyper+a").is_err());
+        assert!(KeyChord::parse("f25").is_err());
+        assert!(KeyChord::parse("enterr").is_err());
+    }
+
+    #[test]
+    fn shifted_events_match_their_character() {
+        let (keymap, conflicts) = Keymap::build(&BTreeMap::new());
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            keymap.action(key(KeyCode::Char('H'), KeyModifiers::SHIFT)),
+            Some(Action::MoveLeft)
+        );
+        assert_eq!(
+            keymap.action(key(KeyCode::Char('#'), KeyModifiers::SHIFT)),
+            Some(Action::EditTags)
+        );
+    }
+
+    #[test]
+    fn overrides_replace_defaults() {
+        let (keymap, conflicts) = Keymap::build(&keys(r#"undo = ["z", "ctrl+z"]"#));
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            keymap.action(key(KeyCode::Char('z'), KeyModifiers::CONTROL)),
+            Some(Action::Undo)
+        );
+        assert_eq!(
+            keymap.action(key(KeyCode::Char('u'), KeyModifiers::NONE)),
+            None
+        );
+
+        let (keymap, _) = Keymap::build(&keys("debug-log = []"));
+        assert!(keymap.keys(Action::DebugLog).is_empty());
+    }
+
+    #[test]
+    fn reports_clashes() {
+        let (keymap, conflicts) = Keymap::build(&keys(r#"export = "r""#));
+        assert_eq!(
+            keymap.action(key(KeyCode::Char('r'), KeyModifiers::NONE)),
+            Some(Action::Export)
+        );
+        assert_eq!(
+            conflicts,
+            vec!["r is now export, edit-label lost its default key"]
+        );
+
+        let (_, conflicts) = Keymap::build(&keys("quit = \"z\"\nundo = \"z\""));
+        assert_eq!(
+            conflicts,
+            vec!["z is bound to both quit and undo, keeping quit"]
+        );
+    }
+
+    #[test]
+    fn bad_entries_only_lose_themselves() {
+        let (keymap, conflicts) = Keymap::build(&keys(
+            "undo = \"z\"\nredo = \"hyper+y\"\nfly = \"f\"\nquit = 3",
+        ));
+        assert_eq!(
+            keymap.action(key(KeyCode::Char('z'), KeyModifiers::NONE)),
+            Some(Action::Undo)
+        );
+        assert_eq!(
+            keymap.action(key(KeyCode::Char('y'), KeyModifiers::CONTROL)),
+            Some(Action::Redo)
+        );
+        assert_eq!(conflicts.len(), 3);
+        assert!(conflicts.contains(&"unknown action 'fly', skipped".to_string()));
+        assert!(conflicts.iter().any(|c| c.starts_with("redo: ")));
+        assert!(conflicts.iter().any(|c| c.starts_with("quit: ")));
+    }
+}
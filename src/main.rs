@@ -1,29 +1,70 @@
-use crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseEventKind};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use crossterm::terminal::enable_raw_mode;
 use ratatui::widgets::ListDirection;
-use std::fs::OpenOptions;
-use std::io::Write;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     prelude::Alignment,
-    style::{Color, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, List, ListState, Padding, Paragraph},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Padding, Paragraph, Row, Table, TableState, Wrap},
     Frame,
 };
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs;
-use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 use tokio::{signal, time};
 use tokio::time::{interval_at, Duration, Instant};
 
+// the smallest box a timer still reads well in, timers that don't fit go on further pages
+const MIN_CELL_WIDTH: u16 = 24;
+const MIN_CELL_HEIGHT: u16 = 7;
+
+mod cli;
+mod command;
+use command::{Command, CommandHistory, Palette, PaletteResult, Target};
+mod config;
+use config::{Config, ConfirmAction};
 mod debug;
 use debug::DebugLog;
+mod dialog;
+use dialog::{Dialog, DialogResult};
+mod digits;
+mod duration;
+use duration::Adjustment;
+mod export;
+mod finder;
+use finder::{Candidate, Finder, FinderResult};
+mod fuzzy;
+mod import;
+mod input;
+use input::LineInput;
+mod keymap;
+use keymap::{Action, Keymap};
 mod mouse;
+use mouse::{ContextMenu, MenuChoice, MenuResult};
+mod notes;
+use notes::{EditorResult, NoteTarget, NotesEditor, SessionList};
+mod report;
+use report::{Grouping, Period};
+mod session;
+use session::Session;
+mod storage;
+use storage::Storage;
+mod tags;
+use tags::TagFilter;
+mod template;
+use template::{Mode, Picker, Template};
+mod timefmt;
+use timefmt::TimeFormat;
+mod trash;
+use trash::{Shelf, ShelfView, TrashedTimer};
+mod undo;
+use undo::{Change, UndoEntry, UndoHistory};
+mod view;
+use view::View;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 struct PersistedTimer {
@@ -31,6 +72,41 @@ struct PersistedTimer {
     elapsed_seconds: u64,
     last_wall_clock: u64, // UNIX timestamp when last saved
     label: Option<String>,
+    #[serde(default = "default_running")]
+    running: bool,
+    #[serde(default)]
+    session_start: Option<u64>, // start of the interval still being recorded
+    #[serde(default)]
+    sessions: Vec<Session>,
+    #[serde(default)]
+    mode: Mode,
+    #[serde(default)]
+    target: Option<u64>, // countdown length or pomodoro round in seconds
+    #[serde(default)]
+    color: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    project: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    notes: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    session_note: String, // for the interval still being recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time_format: Option<String>,
+}
+
+// files saved before pausing existed only had running timers
+fn default_running() -> bool {
+    true
+}
+
+// recorded whenever exclusive mode hands the running slot to another timer
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct SwitchEvent {
+    timestamp: u64,
+    from_timer: Option<usize>, // timer_id that got paused, None if nothing was running
+    to_timer: usize,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -38,6 +114,42 @@ struct PersistedState {
     timers: Vec<PersistedTimer>,
     selected_timer: usize,
     save_timestamp: u64,
+    #[serde(default)]
+    exclusive_mode: bool,
+    #[serde(default)]
+    switch_log: Vec<SwitchEvent>,
+    #[serde(default)]
+    archive: Vec<PersistedTimer>,
+    #[serde(default)]
+    trash: Vec<TrashedTimer>,
+    #[serde(default)]
+    view: View,
+}
+
+impl PersistedState {
+    fn empty(now: u64) -> Self {
+        Self {
+            timers: Vec::new(),
+            selected_timer: 0,
+            save_timestamp: now,
+            exclusive_mode: false,
+            switch_log: Vec::new(),
+            archive: Vec::new(),
+            trash: Vec::new(),
+            view: View::Grid,
+        }
+    }
+
+    // ids stay unique across the grid, the archive and the trash
+    fn next_timer_id(&self) -> usize {
+        self.timers
+            .iter()
+            .chain(&self.archive)
+            .chain(self.trash.iter().map(|t| &t.timer))
+            .map(|t| t.timer_id + 1)
+            .max()
+            .unwrap_or(0)
+    }
 }
 
 #[derive(Clone)]
@@ -49,6 +161,7 @@ struct Time {
     // rtc-based drift check fields
     total_seconds: u64,
     start_wall_clock: u64, // UNIX timestamp when timer started/resumed
+    running: bool,
 }
 
 impl Time {
@@ -61,12 +174,18 @@ impl Time {
             days: 0,
             total_seconds: 0,
             start_wall_clock: now,
+            running: true,
         }
     }
 
     // calculate elapsed time since last save using wall-clock
     fn from_persisted(persisted: &PersistedTimer, now: u64) -> Self {
-        let elapsed_since_save = now.saturating_sub(persisted.last_wall_clock);
+        // paused timers don't accumulate while the app is closed
+        let elapsed_since_save = if persisted.running {
+            now.saturating_sub(persisted.last_wall_clock)
+        } else {
+            0
+        };
         let total_seconds = persisted.elapsed_seconds + elapsed_since_save;
         let original_start = persisted
             .last_wall_clock
@@ -78,6 +197,7 @@ impl Time {
             days: 0,
             total_seconds,
             start_wall_clock: original_start,
+            running: persisted.running,
         };
         time.update_display_fields();
         time
@@ -103,134 +223,1586 @@ impl Time {
         self.update_display_fields();
     }
 
-    fn to_persisted(&self, timer_id: usize, label: &Option<String>) -> PersistedTimer {
-        PersistedTimer {
-            timer_id,
-            elapsed_seconds: self.total_seconds,
-            last_wall_clock: Self::current_unix_time(),
-            label: label.clone(),
-        }
+    fn to_persisted(&self, timer_id: usize, label: &Option<String>) -> PersistedTimer {
+        PersistedTimer {
+            timer_id,
+            elapsed_seconds: self.total_seconds,
+            last_wall_clock: Self::current_unix_time(),
+            label: label.clone(),
+            running: self.running,
+            session_start: None,
+            sessions: Vec::new(),
+            mode: Mode::default(),
+            target: None,
+            color: None,
+            tags: Vec::new(),
+            project: None,
+            notes: String::new(),
+            session_note: String::new(),
+            time_format: None,
+        }
+    }
+}
+
+struct Timer {
+    timer_state: Arc<Mutex<Time>>,
+    label: Option<String>,
+    task_handle: Option<tokio::task::JoinHandle<()>>,
+    timer_id: usize,
+    sessions: Vec<Session>,
+    session_start: Option<u64>,
+    mode: Mode,
+    target: Option<u64>,
+    color: Option<String>,
+    tags: Vec<String>,
+    project: Option<String>,
+    notes: String,
+    session_note: String,
+    time_format: Option<TimeFormat>, // instead of the one from config.toml
+    alert_at: Option<u64>, // elapsed seconds at which a countdown or round ends
+    break_until: Option<u64>, // UNIX timestamp the current pomodoro break is over
+}
+
+impl Timer {
+    fn new(label: Option<String>, timer_id: usize) -> Self {
+        Self {
+            timer_state: Arc::new(Mutex::new(Time::new())),
+            label,
+            task_handle: None,
+            timer_id,
+            sessions: Vec::new(),
+            session_start: Some(Time::current_unix_time()),
+            mode: Mode::default(),
+            target: None,
+            color: None,
+            tags: Vec::new(),
+            project: None,
+            notes: String::new(),
+            session_note: String::new(),
+            time_format: None,
+            alert_at: None,
+            break_until: None,
+        }
+    }
+    // rebuild a timer, running ones get their counter task back
+    fn from_persisted(persisted: PersistedTimer, now: u64) -> Self {
+        let time = Time::from_persisted(&persisted, now);
+        let session_start = if persisted.running {
+            // older saves have no open interval, assume it began when the timer did
+            Some(persisted.session_start.unwrap_or(time.start_wall_clock))
+        } else {
+            None
+        };
+        let alert_at = persisted
+            .mode
+            .next_alert(persisted.target, time.total_seconds);
+        let mut timer = Self {
+            timer_state: Arc::new(Mutex::new(time)),
+            label: persisted.label,
+            task_handle: None,
+            timer_id: persisted.timer_id,
+            sessions: persisted.sessions,
+            session_start,
+            mode: persisted.mode,
+            target: persisted.target,
+            color: persisted.color,
+            tags: persisted.tags,
+            project: persisted.project,
+            notes: persisted.notes,
+            session_note: persisted.session_note,
+            // a format that doesn't parse anymore falls back to the global one
+            time_format: persisted
+                .time_format
+                .and_then(|format| TimeFormat::parse(&format).ok()),
+            alert_at,
+            break_until: None,
+        };
+        if persisted.running {
+            timer.start_counter();
+        }
+        timer
+    }
+
+    fn to_persisted(&self, time_snapshot: &Time) -> PersistedTimer {
+        let mut persisted = time_snapshot.to_persisted(self.timer_id, &self.label);
+        persisted.session_start = self.session_start;
+        persisted.sessions = self.sessions.clone();
+        persisted.mode = self.mode;
+        persisted.target = self.target;
+        persisted.color = self.color.clone();
+        persisted.tags = self.tags.clone();
+        persisted.project = self.project.clone();
+        persisted.notes = self.notes.clone();
+        persisted.session_note = self.session_note.clone();
+        persisted.time_format = self
+            .time_format
+            .as_ref()
+            .map(|format| format.as_str().to_string());
+        persisted
+    }
+
+    fn time_format<'a>(&'a self, config: &'a Config) -> Option<&'a TimeFormat> {
+        self.time_format.as_ref().or(config.time_format.as_ref())
+    }
+
+    // the note of the open session or of a closed one with that start
+    fn note_slot(&mut self, start: u64, adjustment: i64) -> Option<&mut String> {
+        if adjustment == 0 && self.session_start == Some(start) {
+            return Some(&mut self.session_note);
+        }
+        self.sessions
+            .iter_mut()
+            .find(|session| session.start == start && session.adjustment == adjustment)
+            .map(|session| &mut session.note)
+    }
+
+    // after the elapsed time jumps (reset, adjust) the next stop has to be worked out again
+    fn rearm(&mut self, total: u64) {
+        self.alert_at = self.mode.next_alert(self.target, total);
+    }
+
+    // end the current interval and, if still running, open a fresh one at the same instant
+    fn split_session(&mut self) {
+        let now = Time::current_unix_time();
+        let was_open = self.session_start.is_some();
+        session::close_session(
+            &mut self.sessions,
+            &mut self.session_start,
+            &mut self.session_note,
+            now,
+        );
+        if was_open {
+            self.session_start = Some(now);
+        }
+    }
+
+    fn start_counter(&mut self) {
+        let time_counter = Arc::clone(&self.timer_state);
+        let handle = tokio::spawn(async move {
+            hybrid_counter(time_counter).await;
+        });
+        self.task_handle = Some(handle);
+    }
+
+    fn is_running(&self) -> bool {
+        self.task_handle.is_some()
+    }
+
+    fn stop_counter(&mut self) {
+        if let Some(handle) = self.task_handle.take() {
+            handle.abort();
+        }
+    }
+
+    async fn pause(&mut self) {
+        self.stop_counter();
+        self.timer_state.lock().await.running = false;
+        session::close_session(
+            &mut self.sessions,
+            &mut self.session_start,
+            &mut self.session_note,
+            Time::current_unix_time(),
+        );
+    }
+
+    async fn resume(&mut self) {
+        if self.is_running() {
+            return;
+        }
+        {
+            let mut time_guard = self.timer_state.lock().await;
+            // move the start so the drift check in hybrid_counter ignores the paused period
+            time_guard.start_wall_clock =
+                Time::current_unix_time().saturating_sub(time_guard.total_seconds);
+            time_guard.running = true;
+        }
+        self.break_until = None; // starting early skips the rest of the break
+        self.session_start = Some(Time::current_unix_time());
+        self.start_counter();
+    }
+}
+
+// what the text typed into input is for
+#[derive(Clone, Copy, PartialEq)]
+enum InputMode {
+    Label,
+    ExportPath,
+    AdjustTime,
+    Tags,
+    Project,
+    Filter,
+    Command,
+}
+
+impl InputMode {
+    // in graphemes, paths get room for deep directories
+    fn max_len(self) -> usize {
+        match self {
+            Self::Label | Self::Project | Self::Filter => 64,
+            Self::AdjustTime => 32,
+            Self::Tags => 256,
+            Self::ExportPath | Self::Command => 1024,
+        }
+    }
+
+    // the modes that edit the selected timer, typed in its box
+    fn prompt(self) -> Option<&'static str> {
+        match self {
+            Self::Label => Some("Label: "),
+            Self::AdjustTime => Some("Adjust (+15m, -1h30m, =2:45:00): "),
+            Self::Tags => Some("Tags (tab completes): "),
+            Self::Project => Some("Project (tab completes): "),
+            _ => None,
+        }
+    }
+}
+
+struct State {
+    timers: Vec<Timer>,
+    selected_timer: usize,
+    ui_update_rate_ms: u64,
+    input_mode: Option<InputMode>,
+    input: LineInput,
+    show_help: bool,
+    show_dbg: bool,
+    next_timer_id: usize,
+    save_interval_seconds: u64,
+    last_save_time: u64,
+    exclusive_mode: bool, // only one timer may run at a time
+    switch_log: Vec<SwitchEvent>,
+    show_report: bool,
+    report_period: Period,
+    report_offset: i64,
+    report_grouping: Grouping,
+    filter: Option<TagFilter>, // only timers matching it are on the grid
+    storage: Box<dyn Storage>,
+    history: UndoHistory,
+    toast: Option<(String, std::time::Instant)>, // message and when it was shown
+    config: Config,
+    dialog: Option<Dialog<ConfirmAction>>,
+    archive: Vec<PersistedTimer>, // off the grid but still in reports
+    trash: Vec<TrashedTimer>,
+    shelf_view: Option<ShelfView>,
+    picker: Option<Picker>,
+    notes_editor: Option<NotesEditor>,
+    session_list: Option<SessionList>,
+    keymap: Keymap,
+    palette: Option<Palette>,
+    command_history: CommandHistory,
+    finder: Option<Finder>,
+    grid_areas: Vec<Rect>, // where the timers on the page were last drawn, for mouse and arrow keys
+    page_size: usize,      // how many timers fit on a page at the last draw
+    view: View,
+    dragging: Option<(usize, usize)>, // timer picked up with the mouse and the one it's over
+    menu: Option<ContextMenu>,
+    last_click: Option<(usize, std::time::Instant)>, // for telling double clicks apart
+}
+
+impl State {
+    fn new(config: Config, storage: Box<dyn Storage>) -> Self {
+        let args: Vec<String> = env::args().collect();
+        let initial_label = if args.len() == 2 {
+            Some(args[1].clone())
+        } else {
+            None
+        };
+        let (keymap, conflicts) = Keymap::build(&config.keys);
+        for conflict in &conflicts {
+            DebugLog::log(&format!("[keys] in config.toml: {}", conflict));
+        }
+        let toast = conflicts.first().map(|conflict| {
+            let message = format!("[keys] in config.toml: {} (ctrl+p for all)", conflict);
+            (message, std::time::Instant::now())
+        });
+        Self {
+            timers: vec![Timer::new(initial_label, 0)],
+            selected_timer: 0,
+            ui_update_rate_ms: 27, // 37 fps by default, 20 feels too sluggish imo
+            input_mode: None,
+            input: LineInput::default(),
+            show_help: true,
+            show_dbg: false,
+            next_timer_id: 1,
+            save_interval_seconds: 180, // autosave every 3 minutes
+            last_save_time: Time::current_unix_time(),
+            exclusive_mode: false,
+            switch_log: Vec::new(),
+            show_report: false,
+            report_period: Period::Day,
+            report_offset: 0,
+            report_grouping: Grouping::Label,
+            filter: None,
+            storage,
+            history: UndoHistory::default(),
+            toast,
+            config,
+            dialog: None,
+            archive: Vec::new(),
+            trash: Vec::new(),
+            shelf_view: None,
+            picker: None,
+            notes_editor: None,
+            session_list: None,
+            keymap,
+            palette: None,
+            command_history: CommandHistory::default(),
+            finder: None,
+            grid_areas: Vec::new(),
+            page_size: 1,
+            view: View::Grid,
+            dragging: None,
+            menu: None,
+            last_click: None,
+        }
+    }
+
+    async fn reset_timer(&mut self) {
+        let change = self.reset_at(self.selected_timer).await;
+        self.record_undo("reset", change);
+    }
+
+    // back to zero, returns what undoes it
+    async fn reset_at(&mut self, idx: usize) -> Change {
+        // the value before the reset stays in the session history
+        self.timers[idx].split_session();
+        let seconds = self.set_elapsed(idx, |_| 0).await;
+        self.timers[idx].break_until = None;
+        Change::Time {
+            seconds: -seconds,
+            add: Vec::new(),
+            remove: Vec::new(),
+        }
+    }
+
+    // ctrl+a, straight to a blank timer unless there are templates to choose from
+    async fn new_timer(&mut self) {
+        if self.config.templates.is_empty() {
+            self.add_timer(None).await;
+        } else {
+            self.picker = Some(Picker::new());
+        }
+    }
+
+    async fn add_timer(&mut self, template: Option<Template>) {
+        let timer_id = self.next_timer_id;
+        self.next_timer_id += 1;
+        let timer = match template {
+            Some(template) => {
+                let now = Time::current_unix_time();
+                Timer::from_persisted(template.to_persisted(timer_id, now), now)
+            }
+            None => {
+                let mut timer = Timer::new(None, timer_id);
+                timer.start_counter();
+                timer
+            }
+        };
+        self.timers.push(timer);
+        self.reveal(self.timers.len() - 1);
+        if self.exclusive_mode {
+            self.switch_to(self.selected_timer).await;
+        }
+    }
+
+    // stop countdowns that ran out and move pomodoro timers between rounds and breaks
+    async fn check_alerts(&mut self, time_snapshots: &[Time]) {
+        let now = Time::current_unix_time();
+        for (idx, snapshot) in time_snapshots.iter().enumerate() {
+            let timer = &mut self.timers[idx];
+            let name = timer
+                .label
+                .clone()
+                .unwrap_or_else(|| format!("Timer {}", idx + 1));
+            if timer.break_until.is_some_and(|until| now >= until) {
+                timer.break_until = None;
+                self.show_toast(format!("{}: break is over, space starts the next round", name));
+                continue;
+            }
+            let Some(alert_at) = timer.alert_at else {
+                continue;
+            };
+            if !timer.is_running() || snapshot.total_seconds < alert_at {
+                continue;
+            }
+            timer.pause().await;
+            let message = match timer.mode {
+                Mode::Pomodoro => {
+                    let round = Mode::round_length(timer.target);
+                    timer.break_until = Some(now + round / 5);
+                    timer.rearm(alert_at);
+                    format!("{}: round {} done, take a break", name, alert_at / round)
+                }
+                _ => {
+                    timer.alert_at = None;
+                    format!("{}: time is up", name)
+                }
+            };
+            self.show_toast(message);
+        }
+    }
+
+    // pause the timer and take it off the grid
+    async fn take_timer(&mut self, idx: usize) -> PersistedTimer {
+        self.timers[idx].pause().await;
+        let persisted = self.capture(idx).await;
+        self.timers.remove(idx);
+        if self.selected_timer >= self.timers.len() {
+            self.selected_timer = self.timers.len() - 1;
+        }
+        self.keep_selection_visible();
+        persisted
+    }
+
+    async fn remove_timer(&mut self) {
+        if self.timers.len() > 1 {
+            self.record_undo("deleted", Change::Deleted(self.selected_timer));
+            self.trash_timer(self.selected_timer).await;
+        }
+    }
+
+    async fn trash_timer(&mut self, idx: usize) {
+        let timer = self.take_timer(idx).await;
+        self.trash.push(TrashedTimer {
+            timer,
+            deleted_at: Time::current_unix_time(),
+        });
+    }
+
+    async fn archive_timer(&mut self) {
+        if self.timers.len() > 1 {
+            let position = self.selected_timer + 1;
+            let timer = self.take_timer(self.selected_timer).await;
+            self.archive.push(timer);
+            self.show_toast(format!("Timer {} archived — b to see the archive", position));
+        }
+    }
+
+    fn toggle_shelf_view(&mut self) {
+        self.shelf_view = match self.shelf_view {
+            Some(_) => None,
+            None => Some(ShelfView::new()),
+        };
+    }
+
+    fn shelf_len(&self, shelf: Shelf) -> usize {
+        match shelf {
+            Shelf::Archive => self.archive.len(),
+            Shelf::Trash => self.trash.len(),
+        }
+    }
+
+    fn move_shelf_selection(&mut self, delta: isize) {
+        if let Some(shelf) = self.shelf_view.as_ref().map(|view| view.shelf) {
+            let len = self.shelf_len(shelf);
+            if let Some(view) = &mut self.shelf_view {
+                view.move_selection(delta, len);
+            }
+        }
+    }
+
+    // remove the highlighted row from whichever shelf is focused
+    fn take_from_shelf(&mut self) -> Option<PersistedTimer> {
+        let (shelf, selected) = self
+            .shelf_view
+            .as_ref()
+            .map(|view| (view.shelf, view.selected))?;
+        if selected >= self.shelf_len(shelf) {
+            return None;
+        }
+        let timer = match shelf {
+            Shelf::Archive => self.archive.remove(selected),
+            Shelf::Trash => self.trash.remove(selected).timer,
+        };
+        self.move_shelf_selection(0);
+        Some(timer)
+    }
+
+    // put the highlighted archived or trashed timer back on the grid, paused
+    fn restore_from_shelf(&mut self) {
+        if let Some(timer) = self.take_from_shelf() {
+            let label = report::timer_label(&timer);
+            self.timers
+                .push(Timer::from_persisted(timer, Time::current_unix_time()));
+            self.show_toast(format!("{} restored", label));
+            self.reveal(self.timers.len() - 1);
+        }
+    }
+
+    fn purge_from_shelf(&mut self) {
+        if let Some(timer) = self.take_from_shelf() {
+            self.show_toast(format!("{} purged", report::timer_label(&timer)));
+        }
+    }
+
+    fn toggle_debug(&mut self) {
+        self.show_dbg = !self.show_dbg;
+    }
+
+    fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+    }
+
+    fn toggle_report(&mut self) {
+        self.show_report = !self.show_report;
+        self.report_offset = 0;
+    }
+
+    fn set_report_period(&mut self, period: Period) {
+        self.report_period = period;
+        self.report_offset = 0;
+    }
+
+    async fn next_timer(&mut self) {
+        self.cycle_selection(1).await;
+    }
+
+    // step through the visible timers, wrapping around at either end
+    async fn cycle_selection(&mut self, delta: isize) {
+        let visible = self.visible_timers();
+        if visible.is_empty() {
+            return;
+        }
+        let next = match visible.iter().position(|idx| *idx == self.selected_timer) {
+            Some(pos) => (pos as isize + delta).rem_euclid(visible.len() as isize) as usize,
+            None if delta < 0 => visible.len() - 1,
+            None => 0,
+        };
+        self.select_timer(visible[next]).await;
+    }
+
+    // arrows and hjkl move between the boxes as they were last drawn
+    async fn select_towards(&mut self, dx: i32, dy: i32) {
+        if self.view == View::Focus {
+            // one timer on screen, any direction goes through them in order
+            self.cycle_selection((dx + dy).signum() as isize).await;
+            return;
+        }
+        let visible = self.visible_timers();
+        let Some(from) = visible.iter().position(|idx| *idx == self.selected_timer) else {
+            return;
+        };
+        let start = self.page_start(&visible);
+        if let Some(&idx) = neighbour(&self.grid_areas, from - start, dx, dy).and_then(|to| visible.get(start + to)) {
+            self.select_timer(idx).await;
+        }
+    }
+
+    fn set_view(&mut self, view: View) {
+        self.view = view;
+        self.show_toast(format!("Switched to the {} view", view.name()));
+    }
+
+    // offset into the visible timers of the page the selection is on
+    fn page_start(&self, visible: &[usize]) -> usize {
+        let pos = visible
+            .iter()
+            .position(|idx| *idx == self.selected_timer)
+            .unwrap_or(0);
+        pos - pos % self.page_size.max(1)
+    }
+
+    // pgup/pgdn, the first timer of the page before or after becomes the selection
+    async fn turn_page(&mut self, delta: isize) {
+        let visible = self.visible_timers();
+        let start = self.page_start(&visible);
+        let target = if delta < 0 {
+            start.checked_sub(self.page_size)
+        } else {
+            Some(start + self.page_size).filter(|pos| *pos < visible.len())
+        };
+        if let Some(pos) = target {
+            self.select_timer(visible[pos]).await;
+        }
+    }
+
+    // take the timer out and put it in the place of the one at `to`, the selection goes with it;
+    // the grid order is the order of self.timers, so it's saved as is
+    fn move_timer(&mut self, from: usize, to: usize) {
+        if from == to || to >= self.timers.len() {
+            return;
+        }
+        let order = self.timers.iter().map(|timer| timer.timer_id).collect();
+        self.selected_timer = from;
+        self.record_undo("moved", Change::Order(order));
+        let timer = self.timers.remove(from);
+        self.timers.insert(to, timer);
+        self.selected_timer = to;
+    }
+
+    // swap places with the previous or next timer on the grid, hidden ones are skipped
+    fn shift_timer(&mut self, delta: isize) {
+        let visible = self.visible_timers();
+        let to = visible
+            .iter()
+            .position(|idx| *idx == self.selected_timer)
+            .and_then(|pos| pos.checked_add_signed(delta))
+            .and_then(|pos| visible.get(pos));
+        if let Some(&to) = to {
+            self.move_timer(self.selected_timer, to);
+        }
+    }
+
+    // click selects, dragging a timer onto another one moves it there, a double click edits
+    // the label and a right click opens the context menu; the wheel cycles the selection,
+    // with ctrl or shift held it moves the target of the countdown under the pointer
+    async fn handle_mouse(&mut self, mouse_event: MouseEvent, visible: &[usize]) {
+        if let Some(menu) = &mut self.menu {
+            match menu.handle_mouse(mouse_event) {
+                MenuResult::Chosen(choice) => self.choose_from_menu(choice).await,
+                MenuResult::Close => self.menu = None,
+                MenuResult::Open => {}
+            }
+            return;
+        }
+        let start = self.page_start(visible);
+        let cell = mouse::hit_test(mouse_event.column, mouse_event.row, &self.grid_areas)
+            .and_then(|pos| visible.get(start + pos).copied())
+            .filter(|idx| *idx < self.timers.len());
+        let nudge = mouse_event
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+        match (mouse_event.kind, cell) {
+            (MouseEventKind::Down(MouseButton::Left), Some(idx)) => {
+                self.select_timer(idx).await;
+                let double = self
+                    .last_click
+                    .take()
+                    .is_some_and(|(last, at)| last == idx && at.elapsed() < Duration::from_millis(400));
+                if double {
+                    self.dragging = None;
+                    self.start_input(InputMode::Label);
+                } else {
+                    self.dragging = Some((idx, idx));
+                    self.last_click = Some((idx, std::time::Instant::now()));
+                }
+            }
+            (MouseEventKind::Down(MouseButton::Right), Some(idx)) => {
+                self.select_timer(idx).await;
+                self.cancel_input();
+                self.menu = Some(ContextMenu::new(
+                    idx,
+                    mouse_event.column,
+                    mouse_event.row,
+                    self.timers[idx].is_running(),
+                    &self.keymap,
+                ));
+            }
+            (MouseEventKind::Down(_), Some(idx)) => self.select_timer(idx).await,
+            // the report and the shelf aren't the grid, the selection can't be seen there
+            (MouseEventKind::ScrollUp | MouseEventKind::ScrollDown, _) if self.grid_areas.is_empty() => {}
+            (MouseEventKind::ScrollUp | MouseEventKind::ScrollDown, Some(idx)) if nudge => {
+                let minutes = if mouse_event.kind == MouseEventKind::ScrollUp { 1 } else { -1 };
+                self.nudge_target(idx, minutes).await;
+            }
+            (MouseEventKind::ScrollUp, _) => self.cycle_selection(-1).await,
+            (MouseEventKind::ScrollDown, _) => self.cycle_selection(1).await,
+            (MouseEventKind::Drag(MouseButton::Left), Some(idx)) => {
+                if let Some((_, over)) = &mut self.dragging {
+                    *over = idx;
+                }
+            }
+            (MouseEventKind::Up(MouseButton::Left), cell) => {
+                if let (Some((from, _)), Some(to)) = (self.dragging.take(), cell) {
+                    self.move_timer(from, to);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    async fn choose_from_menu(&mut self, choice: MenuChoice) {
+        let Some(mut menu) = self.menu.take() else {
+            return;
+        };
+        if menu.timer >= self.timers.len() {
+            return;
+        }
+        self.select_timer(menu.timer).await;
+        match choice {
+            MenuChoice::Run(action) => {
+                self.run_action(action).await;
+            }
+            MenuChoice::Colors => {
+                menu.show_colors();
+                self.menu = Some(menu);
+            }
+            MenuChoice::Color(color) => {
+                let color = color.map(str::to_string);
+                let timer = &mut self.timers[menu.timer];
+                if timer.color != color {
+                    let before = std::mem::replace(&mut timer.color, color);
+                    self.record_undo("recolored", Change::Color(before));
+                }
+            }
+        }
+    }
+
+    // a minute more or less per wheel notch, the stop is worked out again right away
+    async fn nudge_target(&mut self, idx: usize, minutes: i64) {
+        let timer = &mut self.timers[idx];
+        let current = match timer.mode {
+            Mode::CountUp => {
+                self.show_toast(format!("Timer {} counts up, it has no target to change", idx + 1));
+                return;
+            }
+            Mode::Countdown => timer.target.unwrap_or(0),
+            Mode::Pomodoro => Mode::round_length(timer.target),
+        };
+        let target = current.saturating_add_signed(minutes * 60).max(60);
+        let before = timer.target.replace(target);
+        let total = timer.timer_state.lock().await.total_seconds;
+        timer.rearm(total);
+        self.selected_timer = idx;
+        let action = format!("target {}", report::format_duration(target));
+        self.record_undo(&action, Change::Target(before));
+    }
+
+    // every timer on the grid, hidden ones too, as the finder lists them
+    fn finder_candidates(&self, time_snapshots: &[Time]) -> Vec<Candidate> {
+        self.timers
+            .iter()
+            .zip(time_snapshots)
+            .enumerate()
+            .map(|(idx, (timer, snapshot))| {
+                let label = timer.label.as_deref().unwrap_or("unlabeled");
+                let categories = categories(timer);
+                let paused = if snapshot.running { "" } else { " paused" };
+                Candidate {
+                    index: idx,
+                    title: format!("{:>2}  {} {}", idx + 1, label, categories)
+                        .trim_end()
+                        .to_string(),
+                    search: format!(
+                        "{} {} {} id{}",
+                        idx + 1,
+                        label,
+                        categories,
+                        timer.timer_id
+                    ),
+                    detail: format!("{}{}", clock_text(timer, snapshot.total_seconds, &self.config), paused),
+                    color: template::parse_color(&timer.color),
+                }
+            })
+            .collect()
+    }
+
+    // indices of the timers that pass the filter, in grid order
+    fn visible_timers(&self) -> Vec<usize> {
+        (0..self.timers.len())
+            .filter(|idx| self.is_visible(*idx))
+            .collect()
+    }
+
+    fn is_visible(&self, idx: usize) -> bool {
+        let timer = &self.timers[idx];
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches(&timer.tags, timer.project.as_deref()))
+    }
+
+    // select a timer that just showed up, dropping a filter that would hide it
+    fn reveal(&mut self, idx: usize) {
+        self.selected_timer = idx;
+        if !self.is_visible(idx) {
+            self.filter = None;
+            self.show_toast(format!("Filter cleared to show Timer {}", idx + 1));
+        }
+    }
+
+    // the selection moves to the nearest timer still on the grid
+    fn keep_selection_visible(&mut self) {
+        let visible = self.visible_timers();
+        if let Some(&idx) = visible
+            .iter()
+            .find(|idx| **idx >= self.selected_timer)
+            .or(visible.last())
+        {
+            self.selected_timer = idx;
+        }
+    }
+
+    async fn set_filter(&mut self, filter: Option<TagFilter>) {
+        self.filter = filter;
+        match (&self.filter, self.visible_timers().first()) {
+            (None, _) => self.show_toast("Showing all timers".to_string()),
+            (Some(filter), None) => {
+                self.show_toast(format!("No timer matches {}", filter.describe()))
+            }
+            (Some(filter), Some(&first)) => {
+                let message = format!("Showing {} — f then enter to clear", filter.describe());
+                self.show_toast(message);
+                if !self.is_visible(self.selected_timer) {
+                    self.select_timer(first).await;
+                }
+            }
+        }
+    }
+
+    // every tag and project in use, for tab completion
+    fn known_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .timers
+            .iter()
+            .flat_map(|timer| timer.tags.iter())
+            .chain(self.archive.iter().flat_map(|timer| timer.tags.iter()))
+            .chain(self.config.templates.iter().flat_map(|t| t.tags.iter()))
+            .cloned()
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    fn known_projects(&self) -> Vec<String> {
+        let mut projects: Vec<String> = self
+            .timers
+            .iter()
+            .filter_map(|timer| timer.project.clone())
+            .chain(self.archive.iter().filter_map(|timer| timer.project.clone()))
+            .chain(self.config.templates.iter().filter_map(|t| t.project.clone()))
+            .collect();
+        projects.sort();
+        projects.dedup();
+        projects
+    }
+
+    fn complete_input(&mut self) {
+        let buffer = self.input.text();
+        let completed = match self.input_mode {
+            Some(InputMode::Tags) => tags::complete_word(buffer, &self.known_tags()),
+            Some(InputMode::Project) => tags::complete_value(buffer, &self.known_projects()),
+            Some(InputMode::Filter) => {
+                let candidates = match buffer.chars().next() {
+                    Some('#') => self.known_tags(),
+                    Some('@') => self.known_projects(),
+                    _ => [self.known_tags(), self.known_projects()].concat(),
+                };
+                tags::complete_word(buffer, &candidates)
+            }
+            // the command name first, then what its argument can be
+            Some(InputMode::Command) => {
+                let candidates = match buffer.split_once(' ').map(|(name, _)| name) {
+                    None => command::names(),
+                    Some("add") => self.config.templates.iter().map(|t| t.name.clone()).collect(),
+                    Some("tags") => self.known_tags(),
+                    Some("project") => self.known_projects(),
+                    Some("filter") => [self.known_tags(), self.known_projects()].concat(),
+                    Some(_) => Vec::new(),
+                };
+                tags::complete_word(buffer, &candidates)
+            }
+            _ => None,
+        };
+        if let Some(completed) = completed {
+            self.input.set(&completed);
+        }
+    }
+
+    async fn select_timer(&mut self, idx: usize) {
+        self.selected_timer = idx;
+        if self.exclusive_mode {
+            self.switch_to(idx).await;
+        }
+    }
+
+    // pause every other timer and make sure idx is the one running
+    async fn switch_to(&mut self, idx: usize) {
+        let to_timer = self.timers[idx].timer_id;
+        let mut from_timer = None;
+        for (i, timer) in self.timers.iter_mut().enumerate() {
+            if i != idx && timer.is_running() {
+                from_timer.get_or_insert(timer.timer_id);
+                timer.pause().await;
+            }
+        }
+        let was_running = self.timers[idx].is_running();
+        self.timers[idx].resume().await;
+        if from_timer.is_some() || !was_running {
+            self.switch_log.push(SwitchEvent {
+                timestamp: Time::current_unix_time(),
+                from_timer,
+                to_timer,
+            });
+            DebugLog::log(&format!("switched {:?} -> {}", from_timer, to_timer));
+        }
+    }
+
+    async fn toggle_pause(&mut self) {
+        let idx = self.selected_timer;
+        if self.timers[idx].is_running() {
+            self.timers[idx].pause().await;
+        } else if self.exclusive_mode {
+            self.switch_to(idx).await;
+        } else {
+            self.timers[idx].resume().await;
+        }
+    }
+
+    // ask first if the config wants that, returns true when the app should quit
+    async fn request(&mut self, action: ConfirmAction) -> bool {
+        if !self.config.needs_confirmation(action) {
+            return self.perform(action).await;
+        }
+        let message = match action {
+            ConfirmAction::Quit => "Quit stopwatch?".to_string(),
+            ConfirmAction::Reset => format!("Reset Timer {}?", self.selected_timer + 1),
+            ConfirmAction::ResetAll => format!("Reset all {} timers?", self.timers.len()),
+            ConfirmAction::Delete => {
+                format!("Move Timer {} to the trash?", self.selected_timer + 1)
+            }
+            ConfirmAction::Purge => "Delete it for good? This can't be undone.".to_string(),
+        };
+        self.dialog = Some(Dialog::new(message, action));
+        false
+    }
+
+    async fn perform(&mut self, action: ConfirmAction) -> bool {
+        match action {
+            ConfirmAction::Quit => return true,
+            ConfirmAction::Reset => self.reset_timer().await,
+            ConfirmAction::Delete => self.remove_timer().await,
+            ConfirmAction::Purge => self.purge_from_shelf(),
+            ConfirmAction::ResetAll => {
+                // one undo entry for all of them, so it fits the history however many there are
+                let mut changes = Vec::new();
+                for idx in 0..self.timers.len() {
+                    changes.push((self.timers[idx].timer_id, self.reset_at(idx).await));
+                }
+                self.history.record(UndoEntry {
+                    description: "All timers reset".to_string(),
+                    changes,
+                });
+                self.show_toast("All timers reset — press u to undo".to_string());
+            }
+        }
+        false
+    }
+
+    // what a key on the grid does, commands end up here too; true when the app should quit
+    async fn run_action(&mut self, action: Action) -> bool {
+        match action {
+            Action::Quit => return self.request(ConfirmAction::Quit).await,
+            Action::AddTimer => self.new_timer().await,
+            Action::DeleteTimer if self.timers.len() > 1 => {
+                self.request(ConfirmAction::Delete).await;
+            }
+            Action::ResetTimer => {
+                self.request(ConfirmAction::Reset).await;
+            }
+            Action::DebugLog => self.toggle_debug(),
+            Action::Redo => self.redo().await,
+            Action::ToggleHelp => self.toggle_help(),
+            Action::Undo => self.undo().await,
+            Action::ShowReport => self.toggle_report(),
+            Action::BrowseShelf => self.toggle_shelf_view(),
+            Action::ArchiveTimer if self.timers.len() > 1 => self.archive_timer().await,
+            Action::ToggleExclusive => self.toggle_exclusive().await,
+            Action::TogglePause => self.toggle_pause().await,
+            Action::EditLabel => self.start_input(InputMode::Label),
+            Action::Export => self.start_input(InputMode::ExportPath),
+            Action::AdjustTime => self.start_input(InputMode::AdjustTime),
+            Action::EditTags => self.start_input(InputMode::Tags),
+            Action::EditProject => self.start_input(InputMode::Project),
+            Action::Filter => self.start_input(InputMode::Filter),
+            Action::TimerNotes => self.edit_timer_notes(),
+            Action::SessionNotes => self.open_session_list(),
+            Action::FasterUi if self.ui_update_rate_ms > 10 => self.ui_update_rate_ms -= 5,
+            Action::SlowerUi if self.ui_update_rate_ms < 100 => self.ui_update_rate_ms += 5,
+            Action::NextTimer => self.next_timer().await,
+            Action::SelectLeft => self.select_towards(-1, 0).await,
+            Action::SelectRight => self.select_towards(1, 0).await,
+            Action::SelectUp => self.select_towards(0, -1).await,
+            Action::SelectDown => self.select_towards(0, 1).await,
+            Action::MoveLeft => self.shift_timer(-1),
+            Action::MoveRight => self.shift_timer(1),
+            Action::PreviousPage => self.turn_page(-1).await,
+            Action::NextPage => self.turn_page(1).await,
+            Action::FindTimer => self.finder = Some(Finder::new()),
+            Action::CycleView => self.set_view(self.view.next()),
+            Action::CommandLine => self.start_input(InputMode::Command),
+            Action::CommandPalette => self.palette = Some(Palette::new()),
+            _ => {}
+        }
+        false
+    }
+
+    // the timers a command is for, a number also selects that timer
+    fn aim(&mut self, target: Target) -> Vec<usize> {
+        match target {
+            Target::Selected => vec![self.selected_timer],
+            Target::Timer(idx) => {
+                self.reveal(idx);
+                vec![idx]
+            }
+            Target::All => (0..self.timers.len()).collect(),
+        }
+    }
+
+    // a line from the command line or the palette, true when the app should quit
+    async fn run_command(&mut self, line: &str, persisted_timers: &[PersistedTimer]) -> bool {
+        let command = match command::parse(line, self.timers.len()) {
+            Ok(command) => command,
+            Err(e) => {
+                self.show_toast(format!(":{} — {}", line.trim(), e));
+                return false;
+            }
+        };
+        match command {
+            Command::Run(target, action) => {
+                self.aim(target);
+                return self.run_action(action).await;
+            }
+            // a bare add picks a template like ctrl+a does
+            Command::Add(None, None) => self.new_timer().await,
+            Command::Add(name, target) => self.add_from_command(name, target).await,
+            Command::Label(target, text) => {
+                self.aim(target);
+                self.set_label(&text);
+            }
+            Command::Tags(target, text) => {
+                self.aim(target);
+                self.set_tags(&text);
+            }
+            Command::Project(target, text) => {
+                self.aim(target);
+                self.set_project(&text);
+            }
+            Command::Adjust(target, adjustment) => {
+                self.aim(target);
+                self.adjust_time(adjustment).await;
+            }
+            Command::Reset(Target::All) => {
+                self.request(ConfirmAction::ResetAll).await;
+            }
+            Command::Reset(target) => {
+                self.aim(target);
+                self.request(ConfirmAction::Reset).await;
+            }
+            Command::Pause(target) => {
+                for idx in self.aim(target) {
+                    if self.timers[idx].is_running() {
+                        self.timers[idx].pause().await;
+                    }
+                }
+            }
+            Command::Resume(Target::All) if self.exclusive_mode => {
+                self.show_toast("Exclusive mode runs one timer at a time".to_string());
+            }
+            Command::Resume(target) => {
+                for idx in self.aim(target) {
+                    if self.exclusive_mode {
+                        self.switch_to(idx).await;
+                    } else {
+                        self.timers[idx].resume().await;
+                    }
+                }
+            }
+            Command::Select(idx) => {
+                self.reveal(idx);
+                self.select_timer(idx).await;
+            }
+            Command::Move(target, delta) => {
+                self.aim(target);
+                self.shift_timer(delta);
+            }
+            Command::MoveTo(target, to) => {
+                let from = self.aim(target)[0];
+                self.move_timer(from, to);
+            }
+            Command::Exclusive(on) => {
+                if on != Some(self.exclusive_mode) {
+                    self.toggle_exclusive().await;
+                }
+            }
+            Command::Filter(filter) => self.set_filter(filter).await,
+            Command::Report(period) => {
+                if !self.show_report {
+                    self.toggle_report();
+                }
+                if let Some(period) = period {
+                    self.set_report_period(period);
+                }
+            }
+            Command::Export(format, path) => self.export_command(format, path, persisted_timers),
+            Command::Fps(fps) => self.ui_update_rate_ms = 1000 / fps,
+            Command::View(view) => self.set_view(view.unwrap_or(self.view.next())),
+            Command::Format(target, format) => {
+                let shown = format.as_ref().map_or("the default format", TimeFormat::as_str).to_string();
+                for idx in self.aim(target) {
+                    self.timers[idx].time_format = format.clone();
+                }
+                self.show_toast(format!("Time shown as {}", shown));
+            }
+        }
+        false
+    }
+
+    // `:add standup 15m`, a template when one has that name and a label otherwise
+    async fn add_from_command(&mut self, name: Option<String>, target: Option<u64>) {
+        let found = name
+            .as_deref()
+            .and_then(|name| template::find(&self.config.templates, name))
+            .cloned();
+        let mut template = found.unwrap_or(Template {
+            label: name,
+            ..Template::default()
+        });
+        if let Some(target) = target {
+            template.target = Some(target);
+            if template.mode == Mode::CountUp {
+                template.mode = Mode::Countdown;
+            }
+        }
+        self.add_timer(Some(template)).await;
+    }
+
+    fn export_command(
+        &mut self,
+        format: Option<export::Format>,
+        path: Option<String>,
+        persisted_timers: &[PersistedTimer],
+    ) {
+        let format = format.unwrap_or_else(|| {
+            path.as_deref()
+                .map_or(export::Format::Csv, export::Format::from_path)
+        });
+        let path = match (path, storage::data_dir()) {
+            (Some(path), _) => path,
+            (None, Ok(dir)) => dir
+                .join(format!("timers-export.{}", format.extension()))
+                .to_string_lossy()
+                .into_owned(),
+            (None, Err(e)) => {
+                self.show_toast(format!("Export failed: {}", e));
+                return;
+            }
+        };
+        match self.export_to(&path, format, persisted_timers) {
+            Ok(()) => self.show_toast(format!("Exported timers to {}", path)),
+            Err(e) => self.show_toast(format!("Export failed: {}", e)),
+        }
+    }
+
+    fn show_toast(&mut self, message: String) {
+        self.toast = Some((message, std::time::Instant::now()));
+    }
+
+    async fn capture(&self, idx: usize) -> PersistedTimer {
+        let timer = &self.timers[idx];
+        let time_guard = timer.timer_state.lock().await;
+        timer.to_persisted(&time_guard)
+    }
+
+    // remember what an action changed on the selected timer so it can be undone
+    fn record_undo(&mut self, action: &str, change: Change) {
+        let timer_id = self.timers[self.selected_timer].timer_id;
+        let description = format!("Timer {} {}", self.selected_timer + 1, action);
+        self.show_toast(format!("{} — press u to undo", description));
+        self.history.record(UndoEntry {
+            description,
+            changes: vec![(timer_id, change)],
+        });
+    }
+
+    // put back what the entry holds, returns the entry that reverses this
+    async fn restore(&mut self, entry: UndoEntry) -> UndoEntry {
+        let selected = self.timers[self.selected_timer].timer_id;
+        let several = entry.changes.len() > 1;
+        let mut changes = Vec::new();
+        for (timer_id, change) in entry.changes {
+            if let Some(reverse) = self.apply_change(timer_id, change).await {
+                changes.push((timer_id, reverse));
+            }
+        }
+        // one timer gets selected, for all of them the selection stays where it was
+        if let Some(idx) = self
+            .timers
+            .iter()
+            .position(|timer| timer.timer_id == selected)
+            .filter(|_| several)
+        {
+            self.selected_timer = idx;
+        }
+        UndoEntry {
+            description: entry.description,
+            changes,
+        }
+    }
+
+    // None when the timer is gone by now, purged from the trash or deleted since
+    async fn apply_change(&mut self, timer_id: usize, change: Change) -> Option<Change> {
+        let idx = self.timers.iter().position(|timer| timer.timer_id == timer_id);
+        if let Change::Deleted(position) = change {
+            let trashed = self
+                .trash
+                .iter()
+                .position(|trashed| trashed.timer.timer_id == timer_id)?;
+            let timer = self.trash.remove(trashed).timer;
+            let idx = position.min(self.timers.len());
+            self.timers
+                .insert(idx, Timer::from_persisted(timer, Time::current_unix_time()));
+            self.reveal(idx);
+            return Some(Change::Undeleted);
+        }
+        if let Change::Order(order) = change {
+            let current = self.timers.iter().map(|timer| timer.timer_id).collect();
+            // timers added since stay at the end
+            self.timers.sort_by_key(|timer| {
+                order
+                    .iter()
+                    .position(|id| *id == timer.timer_id)
+                    .unwrap_or(usize::MAX)
+            });
+            if let Some(idx) = self.timers.iter().position(|timer| timer.timer_id == timer_id) {
+                self.selected_timer = idx;
+            }
+            return Some(Change::Order(current));
+        }
+        let idx = idx?;
+        self.selected_timer = idx;
+        let timer = &mut self.timers[idx];
+        Some(match change {
+            Change::Label(label) => Change::Label(std::mem::replace(&mut timer.label, label)),
+            Change::Tags(tags) => Change::Tags(std::mem::replace(&mut timer.tags, tags)),
+            Change::Project(project) => {
+                Change::Project(std::mem::replace(&mut timer.project, project))
+            }
+            Change::Color(color) => Change::Color(std::mem::replace(&mut timer.color, color)),
+            Change::Target(target) => {
+                let before = std::mem::replace(&mut timer.target, target);
+                let total = timer.timer_state.lock().await.total_seconds;
+                timer.rearm(total);
+                Change::Target(before)
+            }
+            Change::Notes(notes) => Change::Notes(std::mem::replace(&mut timer.notes, notes)),
+            Change::SessionNote {
+                start,
+                adjustment,
+                note,
+            } => Change::SessionNote {
+                start,
+                adjustment,
+                note: std::mem::replace(timer.note_slot(start, adjustment)?, note),
+            },
+            Change::Time {
+                seconds,
+                add,
+                remove,
+            } => {
+                let removed: Vec<Session> = remove
+                    .iter()
+                    .filter_map(|gone| {
+                        let i = timer.sessions.iter().position(|s| s.same_interval(gone))?;
+                        Some(timer.sessions.remove(i))
+                    })
+                    .collect();
+                timer.sessions.extend(add.iter().cloned());
+                timer.sessions.sort_by_key(|session| session.start);
+                let moved = self
+                    .set_elapsed(idx, |total| total.saturating_add_signed(seconds))
+                    .await;
+                Change::Time {
+                    seconds: -moved,
+                    add: removed,
+                    remove: add,
+                }
+            }
+            Change::Undeleted if self.timers.len() > 1 => {
+                self.trash_timer(idx).await;
+                Change::Deleted(idx)
+            }
+            Change::Undeleted | Change::Deleted(_) | Change::Order(_) => return None,
+        })
+    }
+
+    async fn undo(&mut self) {
+        match self.history.pop_undo() {
+            Some(entry) => {
+                let redo = self.restore(entry).await;
+                if redo.changes.is_empty() {
+                    self.show_toast(format!("Can't undo: {}, the timer is gone", redo.description));
+                } else {
+                    self.show_toast(format!("Undone: {} — ctrl+y to redo", redo.description));
+                    self.history.push_redo(redo);
+                }
+            }
+            None => self.show_toast("Nothing to undo".to_string()),
+        }
+    }
+
+    async fn redo(&mut self) {
+        match self.history.pop_redo() {
+            Some(entry) => {
+                let undo = self.restore(entry).await;
+                if undo.changes.is_empty() {
+                    self.show_toast(format!("Can't redo: {}, the timer is gone", undo.description));
+                } else {
+                    self.show_toast(format!("Redone: {} — press u to undo", undo.description));
+                    self.history.push_undo(undo);
+                }
+            }
+            None => self.show_toast("Nothing to redo".to_string()),
+        }
+    }
+
+    async fn toggle_exclusive(&mut self) {
+        self.exclusive_mode = !self.exclusive_mode;
+        if self.exclusive_mode {
+            self.switch_to(self.selected_timer).await;
+        }
+    }
+
+    fn edit_timer_notes(&mut self) {
+        let timer = &self.timers[self.selected_timer];
+        let title = format!("Notes for Timer {}", self.selected_timer + 1);
+        self.notes_editor = Some(NotesEditor::new(
+            NoteTarget::Timer(timer.timer_id),
+            title,
+            &timer.notes,
+        ));
+    }
+
+    fn open_session_list(&mut self) {
+        self.session_list = Some(SessionList::new(self.timers[self.selected_timer].timer_id));
     }
-}
 
-struct Timer {
-    timer_state: Arc<Mutex<Time>>,
-    label: Option<String>,
-    task_handle: Option<tokio::task::JoinHandle<()>>,
-    timer_id: usize,
-}
+    fn session_rows(&self, timer_id: usize) -> Vec<Option<usize>> {
+        self.timers
+            .iter()
+            .find(|timer| timer.timer_id == timer_id)
+            .map(|timer| notes::session_rows(&timer.sessions, timer.session_start))
+            .unwrap_or_default()
+    }
 
-impl Timer {
-    fn new(label: Option<String>, timer_id: usize) -> Self {
-        Self {
-            timer_state: Arc::new(Mutex::new(Time::new())),
-            label,
-            task_handle: None,
-            timer_id,
+    fn move_session_selection(&mut self, delta: isize) {
+        if let Some(timer_id) = self.session_list.as_ref().map(|list| list.timer_id) {
+            let len = self.session_rows(timer_id).len();
+            if let Some(list) = &mut self.session_list {
+                list.move_selection(delta, len);
+            }
         }
     }
-    fn to_persisted(&self, time_snapshot: &Time) -> PersistedTimer {
-        time_snapshot.to_persisted(self.timer_id, &self.label)
-    }
-}
 
-struct State {
-    timers: Vec<Timer>,
-    selected_timer: usize,
-    ui_update_rate_ms: u64,
-    input_mode: bool,
-    input_buffer: String,
-    show_help: bool,
-    show_dbg: bool,
-    next_timer_id: usize,
-    save_interval_seconds: u64,
-    last_save_time: u64,
-}
+    // open the editor on the session highlighted in the list
+    fn edit_session_note(&mut self) {
+        let Some(list) = self.session_list.take() else {
+            return;
+        };
+        let Some(&row) = self.session_rows(list.timer_id).get(list.selected) else {
+            return;
+        };
+        let Some(timer) = self.timers.iter().find(|t| t.timer_id == list.timer_id) else {
+            return;
+        };
+        let (title, text) = match row {
+            None => ("Note for the running session".to_string(), &timer.session_note),
+            Some(i) => (
+                format!("Note for the session of {}", export::format_timestamp(timer.sessions[i].start)),
+                &timer.sessions[i].note,
+            ),
+        };
+        self.notes_editor = Some(NotesEditor::new(
+            NoteTarget::Session(list.timer_id, row),
+            title,
+            text,
+        ));
+    }
 
-impl State {
-    fn new() -> Self {
-        let args: Vec<String> = env::args().collect();
-        let initial_label = if args.len() == 2 {
-            Some(args[1].clone())
-        } else {
-            None
+    async fn save_note(&mut self) {
+        let Some(editor) = self.notes_editor.take() else {
+            return;
         };
-        Self {
-            timers: vec![Timer::new(initial_label, 0)],
-            selected_timer: 0,
-            ui_update_rate_ms: 27, // 37 fps by default, 20 feels too sluggish imo
-            input_mode: false,
-            input_buffer: String::new(),
-            show_help: true,
-            show_dbg: false,
-            next_timer_id: 1,
-            save_interval_seconds: 180, // autosave every 3 minutes
-            last_save_time: Time::current_unix_time(),
+        let (timer_id, session) = match editor.target {
+            NoteTarget::Timer(timer_id) => (timer_id, None),
+            NoteTarget::Session(timer_id, row) => (timer_id, Some(row)),
+        };
+        let Some(idx) = self.timers.iter().position(|t| t.timer_id == timer_id) else {
+            return;
+        };
+        self.selected_timer = idx;
+        let text = editor.text();
+        let timer = &mut self.timers[idx];
+        let session = match session {
+            None => None,
+            Some(row) => {
+                let found = match row {
+                    // the interval may have been closed while the editor was open
+                    None => timer
+                        .session_start
+                        .map(|start| (start, 0))
+                        .or_else(|| timer.sessions.last().map(|s| (s.start, s.adjustment))),
+                    Some(i) => timer.sessions.get(i).map(|s| (s.start, s.adjustment)),
+                };
+                let Some(found) = found else {
+                    return;
+                };
+                Some(found)
+            }
+        };
+        let slot = match session {
+            None => &mut timer.notes,
+            Some((start, adjustment)) => match timer.note_slot(start, adjustment) {
+                Some(slot) => slot,
+                None => return,
+            },
+        };
+        if *slot == text {
+            return;
+        }
+        let before = std::mem::replace(slot, text);
+        let change = match session {
+            None => Change::Notes(before),
+            Some((start, adjustment)) => Change::SessionNote {
+                start,
+                adjustment,
+                note: before,
+            },
+        };
+        self.record_undo("notes edited", change);
+    }
+
+    fn set_tags(&mut self, input: &str) {
+        let tags = tags::parse_tags(input);
+        let timer = &mut self.timers[self.selected_timer];
+        if timer.tags != tags {
+            let before = std::mem::replace(&mut timer.tags, tags);
+            self.record_undo("retagged", Change::Tags(before));
         }
     }
 
-    async fn reset_timer(&mut self) {
-        let mut time_guard = self.timers[self.selected_timer].timer_state.lock().await;
-        let now = Time::current_unix_time();
-        time_guard.second = 0;
-        time_guard.minute = 0;
-        time_guard.hour = 0;
-        time_guard.days = 0;
-        time_guard.total_seconds = 0;
-        time_guard.start_wall_clock = now;
+    fn set_project(&mut self, project: &str) {
+        let project = Some(project.trim().to_string()).filter(|project| !project.is_empty());
+        let timer = &mut self.timers[self.selected_timer];
+        if timer.project != project {
+            let before = std::mem::replace(&mut timer.project, project);
+            self.record_undo("moved to another project", Change::Project(before));
+        }
     }
 
-    fn add_timer(&mut self) {
-        if self.timers.len() < 24 {
-            let timer_id = self.next_timer_id;
-            self.next_timer_id += 1;
-            self.timers.push(Timer::new(None, timer_id));
-            self.selected_timer = self.timers.len() - 1;
+    fn set_label(&mut self, label: &str) {
+        let label = Some(label.to_string()).filter(|label| !label.is_empty());
+        let timer = &mut self.timers[self.selected_timer];
+        if timer.label != label {
+            let before = std::mem::replace(&mut timer.label, label);
+            self.record_undo("relabeled", Change::Label(before));
         }
     }
 
-    fn remove_timer(&mut self) {
-        if self.timers.len() > 1 {
-            if let Some(handle) = self.timers[self.selected_timer].task_handle.take() {
-                handle.abort();
+    fn start_input(&mut self, mode: InputMode) {
+        self.input = LineInput::new(mode.max_len());
+        let timer = &self.timers[self.selected_timer];
+        match mode {
+            InputMode::ExportPath => {
+                // suggest a file next to timers.toml
+                if let Ok(dir) = storage::data_dir() {
+                    self.input
+                        .set(&dir.join("timers-export.csv").to_string_lossy());
+                }
+            }
+            // tags, project and filter start from what's there so they can be edited
+            InputMode::Tags if !timer.tags.is_empty() => {
+                self.input.set(&format!("{} ", timer.tags.join(" ")));
             }
-            self.timers.remove(self.selected_timer);
-            if self.selected_timer >= self.timers.len() {
-                self.selected_timer = self.timers.len() - 1;
+            InputMode::Project => self.input.set(timer.project.as_deref().unwrap_or_default()),
+            InputMode::Filter => {
+                let filter = self.filter.as_ref().map(TagFilter::describe);
+                self.input.set(&filter.unwrap_or_default());
             }
+            _ => {}
         }
+        self.input_mode = Some(mode);
     }
 
-    fn toggle_debug(&mut self) {
-        self.show_dbg = !self.show_dbg;
+    fn cancel_input(&mut self) {
+        self.input_mode = None;
+        self.input.clear();
+        self.command_history.reset();
     }
 
-    fn toggle_help(&mut self) {
-        self.show_help = !self.show_help;
+    // true when a command asked to quit
+    async fn submit_input(&mut self, persisted_timers: &[PersistedTimer]) -> bool {
+        let input = self.input.text().to_string();
+        let mode = self.input_mode;
+        self.cancel_input();
+        match mode {
+            Some(InputMode::Label) => self.set_label(&input),
+            Some(InputMode::Tags) => self.set_tags(&input),
+            Some(InputMode::Project) => self.set_project(&input),
+            Some(InputMode::Filter) => self.set_filter(TagFilter::parse(&input)).await,
+            Some(InputMode::Command) => {
+                self.command_history.push(&input);
+                return self.run_command(&input, persisted_timers).await;
+            }
+            Some(InputMode::AdjustTime) => match Adjustment::parse(&input) {
+                Ok(adjustment) => self.adjust_time(adjustment).await,
+                Err(e) => self.show_toast(format!("time not adjusted: {}", e)),
+            },
+            Some(InputMode::ExportPath) => {
+                let path = input.trim();
+                let format = export::Format::from_path(path);
+                match self.export_to(path, format, persisted_timers) {
+                    Ok(()) => self.show_toast(format!("Exported timers to {}", path)),
+                    Err(e) => self.show_toast(format!("Export failed: {}", e)),
+                }
+            }
+            None => {}
+        }
+        false
     }
 
-    fn next_timer(&mut self) {
-        if !self.timers.is_empty() {
-            self.selected_timer = (self.selected_timer + 1) % self.timers.len();
+    async fn adjust_time(&mut self, adjustment: Adjustment) {
+        let delta = self
+            .set_elapsed(self.selected_timer, |before| adjustment.apply(before))
+            .await;
+        if delta != 0 {
+            let session = Session::adjustment(Time::current_unix_time(), delta);
+            self.timers[self.selected_timer].sessions.push(session.clone());
+            self.record_undo(
+                "time adjusted",
+                Change::Time {
+                    seconds: -delta,
+                    add: Vec::new(),
+                    remove: vec![session],
+                },
+            );
         }
     }
 
-    fn set_label(&mut self) {
-        if self.input_buffer.is_empty() {
-            self.timers[self.selected_timer].label = None;
-        } else {
-            self.timers[self.selected_timer].label = Some(self.input_buffer.clone());
-        }
-        self.input_buffer.clear();
-        self.input_mode = false;
+    // move a timer's count, returns by how much it moved
+    async fn set_elapsed(&mut self, idx: usize, to: impl FnOnce(u64) -> u64) -> i64 {
+        let now = Time::current_unix_time();
+        let timer = &mut self.timers[idx];
+        let (total, delta) = {
+            let mut time_guard = timer.timer_state.lock().await;
+            let before = time_guard.total_seconds;
+            time_guard.total_seconds = to(before);
+            time_guard.update_display_fields();
+            // keep the drift check in hybrid_counter in line with the new value
+            time_guard.start_wall_clock = now.saturating_sub(time_guard.total_seconds);
+            (
+                time_guard.total_seconds,
+                time_guard.total_seconds as i64 - before as i64,
+            )
+        };
+        timer.rearm(total);
+        delta
     }
 
-    fn get_save_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
-        let exe_path = env::current_exe()?;
-        let exe_dir = exe_path
-            .parent()
-            .unwrap_or_else(|| std::path::Path::new("."));
-        Ok(exe_dir.join("timers.toml"))
+    fn export_to(
+        &self,
+        path: &str,
+        format: export::Format,
+        persisted_timers: &[PersistedTimer],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let data = export::export(
+            persisted_timers,
+            format,
+            &export::Filter::default(),
+            Time::current_unix_time(),
+            self.config.time_format.as_ref(),
+        )?;
+        fs::write(export::expand_home(path), data)?;
+        Ok(())
     }
 
     async fn save_to_disk(&self) -> Result<(), Box<dyn std::error::Error>> {
@@ -243,21 +1815,13 @@ impl State {
             timers: persisted_timers,
             selected_timer: self.selected_timer,
             save_timestamp: Time::current_unix_time(),
+            exclusive_mode: self.exclusive_mode,
+            switch_log: self.switch_log.clone(),
+            view: self.view,
+            archive: self.archive.clone(),
+            trash: self.trash.clone(),
         };
-        let toml_string = toml::to_string_pretty(&state)?;
-        let save_path = Self::get_save_path()?;
-        fs::write(save_path, toml_string)?;
-        Ok(())
-    }
-
-    fn load_from_disk() -> Result<Option<PersistedState>, Box<dyn std::error::Error>> {
-        let save_path = Self::get_save_path()?;
-        if !save_path.exists() {
-            return Ok(None);
-        }
-        let contents = fs::read_to_string(save_path)?;
-        let state: PersistedState = toml::from_str(&contents)?;
-        Ok(Some(state))
+        self.storage.save(&state)
     }
 
     async fn resume_from_persisted(&mut self, persisted: PersistedState) {
@@ -271,27 +1835,45 @@ impl State {
         self.timers.clear();
         // Restore timers from persisted state
         for p_timer in persisted.timers {
-            let time = Time::from_persisted(&p_timer, now);
-            let mut timer = Timer::new(p_timer.label, p_timer.timer_id);
-            *timer.timer_state.lock().await = time;
-
-            // Spawn counter task for resumed timer
-            let time_counter = Arc::clone(&timer.timer_state);
-            let handle = tokio::spawn(async move {
-                hybrid_counter(time_counter).await;
-            });
-            timer.task_handle = Some(handle);
-            self.timers.push(timer);
             if p_timer.timer_id >= self.next_timer_id {
                 self.next_timer_id = p_timer.timer_id + 1;
             }
+            self.timers.push(Timer::from_persisted(p_timer, now));
+        }
+        // an import into an empty file can leave us without any timer
+        if self.timers.is_empty() {
+            let mut timer = Timer::new(None, self.next_timer_id);
+            self.next_timer_id += 1;
+            timer.start_counter();
+            self.timers.push(timer);
         }
         self.selected_timer = persisted
             .selected_timer
             .min(self.timers.len().saturating_sub(1));
+        self.exclusive_mode = persisted.exclusive_mode;
+        self.view = persisted.view;
+        self.switch_log = persisted.switch_log;
+        self.archive = persisted.archive;
+        self.trash = persisted.trash;
+        // ids stay unique across the grid, the archive and the trash
+        for timer in self.archive.iter().chain(self.trash.iter().map(|t| &t.timer)) {
+            self.next_timer_id = self.next_timer_id.max(timer.timer_id + 1);
+        }
+        self.expire_trash();
         self.last_save_time = now;
     }
 
+    fn expire_trash(&mut self) {
+        let purged = trash::expire(
+            &mut self.trash,
+            self.config.trash_days,
+            Time::current_unix_time(),
+        );
+        if purged > 0 {
+            DebugLog::log(&format!("purged {} expired timers from the trash", purged));
+        }
+    }
+
     fn should_save(&self) -> bool {
         let now = Time::current_unix_time();
         now.saturating_sub(self.last_save_time) >= self.save_interval_seconds
@@ -302,6 +1884,15 @@ impl State {
     }
 }
 
+// persisted view of the timers from the snapshots taken for rendering, no locking needed
+fn persist_snapshots(timers: &[Timer], time_snapshots: &[Time]) -> Vec<PersistedTimer> {
+    timers
+        .iter()
+        .zip(time_snapshots)
+        .map(|(timer, snapshot)| timer.to_persisted(snapshot))
+        .collect()
+}
+
 async fn hybrid_counter(time: Arc<Mutex<Time>>) {
     let mut interval = interval_at(
         Instant::now() + Duration::from_secs(1),
@@ -331,22 +1922,88 @@ async fn hybrid_counter(time: Arc<Mutex<Time>>) {
     }
 }
 
-fn get_layout_areas(frame: &Frame, timer_count: usize) -> Vec<Rect> {
-    let area = frame.area();
+// how many boxes of at least the minimum size fit in area
+fn page_capacity(area: Rect) -> usize {
+    let cols = (area.width / MIN_CELL_WIDTH).max(1) as usize;
+    let rows = (area.height / MIN_CELL_HEIGHT).max(1) as usize;
+    cols * rows
+}
+
+// the table rows go inside its border, below the header
+fn table_rows(area: Rect) -> Rect {
+    Rect::new(
+        area.x + 1.min(area.width),
+        area.y + 2.min(area.height),
+        area.width.saturating_sub(2),
+        area.height.saturating_sub(3),
+    )
+}
+
+// the timer list on the left, the selected timer on the right
+fn sidebar_split(area: Rect) -> (Rect, Rect) {
+    let width = (area.width / 3).clamp(24.min(area.width), 40.min(area.width));
+    let list = Rect::new(area.x, area.y, width, area.height);
+    let detail = Rect::new(area.x + width, area.y, area.width - width, area.height);
+    (list, detail)
+}
+
+fn sidebar_rows(area: Rect) -> Rect {
+    let (list, _) = sidebar_split(area);
+    Block::default().borders(Borders::ALL).inner(list)
+}
+
+// how many timers one page of a view holds
+fn view_capacity(view: View, area: Rect) -> usize {
+    match view {
+        View::Grid => page_capacity(area),
+        View::Table => table_rows(area).height.max(1) as usize,
+        View::Focus => 1,
+        View::Sidebar => sidebar_rows(area).height.max(1) as usize,
+    }
+}
+
+// what a click or the arrow keys can land on, one rect per timer on the page
+fn view_areas(view: View, area: Rect, timer_count: usize) -> Vec<Rect> {
+    let rows = |rows: Rect| -> Vec<Rect> {
+        (0..timer_count as u16)
+            .map(|i| Rect::new(rows.x, rows.y + i, rows.width, 1))
+            .collect()
+    };
+    match view {
+        View::Grid => get_layout_areas(area, timer_count),
+        View::Table => rows(table_rows(area)),
+        View::Focus => vec![area],
+        View::Sidebar => rows(sidebar_rows(area)),
+    }
+}
+
+// timer_count is at most page_capacity(area)
+fn get_layout_areas(area: Rect, timer_count: usize) -> Vec<Rect> {
     if timer_count <= 1 {
         return vec![area];
     }
-    // for 2 we just do a horizontal split, vertical with 2x timers looks wrong imo
+    let max_cols = (area.width / MIN_CELL_WIDTH).max(1) as usize;
+    // for 2 we just do a horizontal split, vertical with 2x timers looks wrong imo,
+    // unless the terminal is too narrow for two boxes side by side
     if timer_count == 2 {
+        let direction = if max_cols >= 2 {
+            Direction::Horizontal
+        } else {
+            Direction::Vertical
+        };
         let chunks = Layout::default()
-            .direction(Direction::Horizontal)
+            .direction(direction)
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
             .split(area);
         return vec![chunks[0], chunks[1]];
     }
 
-    // 6 timers √6≈2.44, rounded up to 3 cols for example
-    let cols = (timer_count as f64).sqrt().ceil() as usize;
+    // 6 timers √6≈2.44, rounded up to 3 cols for example; on a full page the minimum
+    // box height can call for more columns than that, as long as they stay wide enough
+    let max_rows = (area.height / MIN_CELL_HEIGHT).max(1) as usize;
+    let cols = ((timer_count as f64).sqrt().ceil() as usize)
+        .max(timer_count.div_ceil(max_rows))
+        .min(max_cols);
 
     let rows = timer_count.div_ceil(cols);
     let row_areas = Layout::default()
@@ -370,6 +2027,30 @@ fn get_layout_areas(frame: &Frame, timer_count: usize) -> Vec<Rect> {
         .collect()
 }
 
+// the closest box in a direction, boxes in line with this one beat closer ones off to the side
+fn neighbour(areas: &[Rect], from: usize, dx: i32, dy: i32) -> Option<usize> {
+    // doubled centers stay whole numbers
+    let center = |r: &Rect| {
+        (
+            r.x as i32 * 2 + r.width as i32,
+            r.y as i32 * 2 + r.height as i32,
+        )
+    };
+    let (fx, fy) = center(areas.get(from)?);
+    areas
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != from)
+        .filter_map(|(i, r)| {
+            let (x, y) = center(r);
+            let along = (x - fx) * dx + (y - fy) * dy;
+            let across = ((x - fx) * dy).abs() + ((y - fy) * dx).abs();
+            (along > 0).then_some((across * 2 + along, i))
+        })
+        .min()
+        .map(|(_, i)| i)
+}
+
 fn draw_timer_box(
     frame: &mut Frame,
     area: Rect,
@@ -379,60 +2060,355 @@ fn draw_timer_box(
     state: &State,
 ) {
     let is_selected = index == state.selected_timer;
-    let border_color = if is_selected {
-        Color::Green
+    let border_color = match state.dragging {
+        Some((from, over)) if over == index && from != index => Color::Yellow, // where it would land
+        _ if is_selected => Color::Green,
+        _ => Color::Gray,
+    };
+    let notes = if timer.notes.is_empty() { "" } else { " ✎" };
+    let title = if time_snapshot.running {
+        format!(" Timer {}{} ", index + 1, notes)
+    } else if timer.break_until.is_some() {
+        format!(" Timer {}{} (break) ", index + 1, notes)
     } else {
-        Color::Gray
+        format!(" Timer {}{} (paused) ", index + 1, notes)
     };
-    let title = format!(" Timer {} ", index + 1);
     let time_block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(border_color))
         .title(title)
         .padding(Padding::uniform(1));
-    let time_display = if state.input_mode && is_selected {
-        format!("Label: {}_", state.input_buffer)
-    } else {
-        let time_str = format!(
-            "{}d:{}h:{}m:{}s",
-            time_snapshot.days, time_snapshot.hour, time_snapshot.minute, time_snapshot.second
+    let text_color = template::parse_color(&timer.color).unwrap_or(Color::Gray);
+    let prompt = state.input_mode.and_then(InputMode::prompt);
+    if let (Some(prompt), true) = (prompt, is_selected) {
+        let inner = time_block.inner(area);
+        frame.render_widget(
+            Paragraph::new("")
+                .style(Style::default().fg(text_color))
+                .block(time_block),
+            area,
         );
-        match &timer.label {
-            None => time_str,
-            Some(label) => format!("{}\n{}", time_str, label),
+        // narrow boxes get the prompt on a line of its own
+        if prompt.len() + 12 > inner.width as usize && inner.height > 1 {
+            frame.render_widget(
+                Paragraph::new(prompt.trim_end()).alignment(Alignment::Center),
+                inner,
+            );
+            let below = Rect::new(inner.x, inner.y + 1, inner.width, 1);
+            state.input.draw(frame, below, "", Alignment::Center);
+        } else {
+            state.input.draw(frame, inner, prompt, Alignment::Center);
+        }
+        return;
+    }
+    let time_display = {
+        let total = time_snapshot.total_seconds;
+        // the seconds that matter in this mode, the sign and the words around them
+        let (seconds, sign, before, after) = match (timer.mode, timer.target) {
+            (Mode::Countdown, Some(target)) if total <= target => (target - total, "", String::new(), "left"),
+            (Mode::Countdown, Some(target)) => (total - target, "+", String::new(), "over"),
+            (Mode::Pomodoro, target) => match timer.break_until {
+                Some(until) => (
+                    until.saturating_sub(Time::current_unix_time()),
+                    "",
+                    "break".to_string(),
+                    "left",
+                ),
+                None => {
+                    let round = Mode::round_length(target);
+                    (round - total % round, "", format!("round {}", total / round + 1), "left")
+                }
+            },
+            _ => (total, "", String::new(), ""),
+        };
+        let mut details = Vec::new();
+        details.extend(timer.label.clone());
+        let categories = categories(timer);
+        if !categories.is_empty() {
+            details.push(categories);
+        }
+        let inner = time_block.inner(area);
+        // big digits when they fit, "round 2" and "left" go on a line below them
+        let caption = format!("{} {}", before, after).trim().to_string();
+        let room = inner
+            .height
+            .saturating_sub(details.len() as u16 + u16::from(!caption.is_empty()));
+        match digits::render(
+            &format!(
+                "{}{}",
+                sign,
+                timer
+                    .time_format(&state.config)
+                    .map_or_else(|| big_clock(seconds), |format| format.format(seconds))
+            ),
+            state.config.digits,
+            inner.width,
+            room,
+        ) {
+            Some(big) => {
+                let mut lines = big;
+                lines.extend(Some(caption).filter(|c| !c.is_empty()));
+                lines.extend(details);
+                let top = (inner.height as usize).saturating_sub(lines.len()) / 2;
+                let mut centered = vec![String::new(); top];
+                centered.extend(lines);
+                centered.join("\n")
+            }
+            None => {
+                let time = format!("{}{}", sign, clock_text(timer, seconds, &state.config));
+                let time = match (before.is_empty(), after.is_empty()) {
+                    (true, true) => time,
+                    (true, false) => format!("{} {}", time, after),
+                    _ => format!("{} · {} {}", before, time, after),
+                };
+                let mut lines = vec![time];
+                lines.extend(details);
+                lines.join("\n")
+            }
         }
     };
     let time_text = Paragraph::new(time_display)
         .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::Gray))
+        .style(Style::default().fg(text_color))
         .block(time_block);
     frame.render_widget(time_text, area);
 }
 
-fn draw_confirmation_prompt(frame: &mut Frame) {
+// 1:02:03, days in front once there are any; what the big digits show
+fn big_clock(total: u64) -> String {
+    let clock = format!("{}:{:02}:{:02}", (total / 3600) % 24, (total / 60) % 60, total % 60);
+    if total >= 86400 {
+        format!("{}d {}", total / 86400, clock)
+    } else {
+        clock
+    }
+}
+
+// the timer's own format, else time_format from config.toml, else 0d:1h:2m:3s
+fn clock_text(timer: &Timer, seconds: u64, config: &Config) -> String {
+    timer
+        .time_format(config)
+        .map_or_else(|| format_clock(seconds), |format| format.format(seconds))
+}
+
+// same shape as the count-up display, for remaining and overtime seconds
+fn format_clock(total: u64) -> String {
+    format!(
+        "{}d:{}h:{}m:{}s",
+        total / 86400,
+        (total / 3600) % 24,
+        (total / 60) % 60,
+        total % 60
+    )
+}
+
+fn draw_input_prompt(frame: &mut Frame, title: &str, input: &LineInput) {
     let area = frame.area();
-    let line = Line::from(vec![
-        Span::raw("Are you sure? "),
-        Span::styled("Y", Style::default().fg(Color::Green)),
-        Span::styled("/", Style::default().fg(Color::Gray)),
-        Span::styled("N", Style::default().fg(Color::Red)),
-    ]);
     let prompt_block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Gray))
-        .title(" Confirmation ");
-    let prompt_paragraph = Paragraph::new(line)
+        .border_style(Style::default().fg(Color::Green))
+        .title(format!(" {} ", title))
+        .title_bottom(Line::from(" enter confirm · esc cancel ").right_aligned());
+    let prompt_paragraph = Paragraph::new("")
         .block(prompt_block)
-        .centered()
         .style(Style::default().fg(Color::Gray).bg(Color::Black));
 
-    let rect_width = area.width / 2;
-    let rect_height = area.height / 4;
-    let x_pos = (area.width - rect_width) / 2;
-    let y_pos = (area.height - rect_height) / 2;
-    let prompt_area = Rect::new(x_pos, y_pos, rect_width, rect_height);
-
+    let rect_width = (area.width * 2 / 3).max(40).min(area.width);
+    let rect_height = 3.min(area.height);
+    let prompt_area = Rect::new(
+        (area.width - rect_width) / 2,
+        (area.height - rect_height) / 2,
+        rect_width,
+        rect_height,
+    );
+    frame.render_widget(Clear, prompt_area);
     frame.render_widget(prompt_paragraph, prompt_area);
+    let inner = Rect::new(
+        prompt_area.x + 1,
+        prompt_area.y + 1,
+        prompt_area.width.saturating_sub(2),
+        prompt_area.height.saturating_sub(2),
+    );
+    input.draw(frame, inner, "", Alignment::Left);
+}
+
+// vim style, along the bottom edge
+fn draw_command_line(frame: &mut Frame, input: &LineInput) {
+    let area = frame.area();
+    let line_area = Rect::new(area.x, area.bottom().saturating_sub(1), area.width, 1.min(area.height));
+    frame.render_widget(Clear, line_area);
+    frame.render_widget(Block::default().style(Style::default().fg(Color::Gray).bg(Color::Black)), line_area);
+    input.draw(frame, line_area, ":", Alignment::Left);
+}
+
+fn timer_state(timer: &Timer, time_snapshot: &Time) -> &'static str {
+    if time_snapshot.running {
+        "running"
+    } else if timer.break_until.is_some() {
+        "break"
+    } else {
+        "paused"
+    }
+}
+
+fn categories(timer: &Timer) -> String {
+    timer
+        .project
+        .iter()
+        .map(|project| format!("@{}", project))
+        .chain(timer.tags.iter().map(|tag| format!("#{}", tag)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// one row per timer on the page, the selected one highlighted
+fn draw_table(frame: &mut Frame, area: Rect, page: &[usize], time_snapshots: &[Time], state: &State) {
+    let rows: Vec<Row> = page
+        .iter()
+        .map(|&i| {
+            let timer = &state.timers[i];
+            let snapshot = &time_snapshots[i];
+            let style = template::parse_color(&timer.color)
+                .map(|color| Style::default().fg(color))
+                .unwrap_or_default();
+            Row::new(vec![
+                format!("{}", i + 1),
+                format!("{}", timer.timer_id),
+                timer.label.clone().unwrap_or_default(),
+                clock_text(timer, snapshot.total_seconds, &state.config),
+                timer_state(timer, snapshot).to_string(),
+                categories(timer),
+            ])
+            .style(style)
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(4),
+            Constraint::Length(5),
+            Constraint::Fill(2),
+            Constraint::Length(14),
+            Constraint::Length(8),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(
+        Row::new(vec!["#", "id", "label", "elapsed", "state", "tags"])
+            .style(Style::default().fg(Color::DarkGray)),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray))
+            .title(" Timers "),
+    )
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut table_state = TableState::default();
+    table_state.select(page.iter().position(|i| *i == state.selected_timer));
+    frame.render_stateful_widget(table, area, &mut table_state);
+    // the editing prompts live in the timer boxes, which this view doesn't have
+    if let Some(prompt) = state.input_mode.and_then(InputMode::prompt) {
+        draw_input_prompt(frame, prompt.trim_end_matches([':', ' ']), &state.input);
+    }
+}
+
+// the timers listed on the left, the selected one in full on the right with its notes below
+fn draw_sidebar(frame: &mut Frame, area: Rect, page: &[usize], time_snapshots: &[Time], state: &State) {
+    let (list_area, detail_area) = sidebar_split(area);
+    let width = sidebar_rows(area).width as usize;
+    let items: Vec<ListItem> = page
+        .iter()
+        .map(|&i| {
+            let timer = &state.timers[i];
+            let name = timer.label.clone().unwrap_or_else(|| format!("Timer {}", i + 1));
+            let time = clock_text(timer, time_snapshots[i].total_seconds, &state.config);
+            let name_width = width.saturating_sub(time.chars().count() + 4);
+            let name: String = name.chars().take(name_width).collect();
+            let pad = width.saturating_sub(name.chars().count() + time.chars().count() + 3);
+            let style = template::parse_color(&timer.color)
+                .map(|color| Style::default().fg(color))
+                .unwrap_or_default();
+            ListItem::new(format!("{:>2} {}{}{}", i + 1, name, " ".repeat(pad), time)).style(style)
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Gray))
+                .title(" Timers "),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut list_state = ListState::default();
+    list_state.select(page.iter().position(|i| *i == state.selected_timer));
+    frame.render_stateful_widget(list, list_area, &mut list_state);
+
+    let Some(&i) = page.iter().find(|i| **i == state.selected_timer).or(page.first()) else {
+        return;
+    };
+    let timer = &state.timers[i];
+    let mut box_area = detail_area;
+    if !timer.notes.is_empty() && detail_area.height > MIN_CELL_HEIGHT * 2 {
+        let notes_height = (timer.notes.lines().count() as u16 + 2).min(detail_area.height / 2);
+        box_area.height -= notes_height;
+        let notes_area = Rect::new(detail_area.x, box_area.bottom(), detail_area.width, notes_height);
+        frame.render_widget(
+            Paragraph::new(timer.notes.as_str())
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title(" Notes ")),
+            notes_area,
+        );
+    }
+    draw_timer_box(frame, box_area, timer, &time_snapshots[i], i, state);
+}
+
+// "page 2/3 · 25–48 of 60 · pgup/pgdn" on the bottom row
+fn draw_page_indicator(frame: &mut Frame, start: usize, page_size: usize, total: usize, keymap: &Keymap) {
+    let area = frame.area();
+    let mut text = format!(
+        "page {}/{} · {}–{} of {}",
+        start / page_size + 1,
+        total.div_ceil(page_size),
+        start + 1,
+        (start + page_size).min(total),
+        total
+    );
+    let keys: Vec<String> = [Action::PreviousPage, Action::NextPage]
+        .iter()
+        .filter_map(|action| keymap.keys(*action).first().map(|key| key.to_string()))
+        .collect();
+    if !keys.is_empty() {
+        text = format!("{} · {}", text, keys.join("/"));
+    }
+    let row = Rect::new(area.x, area.bottom().saturating_sub(1), area.width, 1.min(area.height));
+    frame.render_widget(
+        Paragraph::new(text)
+            .centered()
+            .style(Style::default().fg(Color::DarkGray)),
+        row,
+    );
+}
+
+fn draw_toast(frame: &mut Frame, message: &str) {
+    let area = frame.area();
+    let width = (message.chars().count() as u16 + 4).min(area.width);
+    let toast_area = Rect::new(
+        (area.width - width) / 2,
+        area.height.saturating_sub(4),
+        width,
+        3.min(area.height),
+    );
+    let toast = Paragraph::new(message)
+        .centered()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .style(Style::default().fg(Color::Gray).bg(Color::Black));
+    frame.render_widget(Clear, toast_area);
+    frame.render_widget(toast, toast_area);
 }
 
 fn draw_debug_box(frame: &mut Frame) {
@@ -468,36 +2444,34 @@ fn draw_debug_box(frame: &mut Frame) {
     frame.render_stateful_widget(dbg_list, dbg_area, &mut list_state);
 }
 
-fn draw_help(frame: &mut Frame, update_rate: u64) {
+fn draw_help(frame: &mut Frame, state: &State) {
     let area = frame.area();
-    let fps = 1000 / update_rate;
-    let help_text = vec![
-        "Shortcuts:",
-        " ctrl + q - Quit",
-        " ctrl + a - Add timer (max 16)",
-        " ctrl + d - Delete selected timer",
-        " ctrl + r - Reset selected timer",
-        " tab - Next timer",
-        " l - Set label for timer",
-        " h - Toggle help",
-        " ↑/↓ - Increase/Decrease UI FPS",
-        " esc - Cancel input",
-        " ",
-        " ctrl + p - Show debug log",
-    ];
+    let fps = 1000 / state.ui_update_rate_ms;
+    // generated from the keymap so it shows what the keys really do
+    let mut help_text = vec!["Shortcuts:".to_string()];
+    help_text.extend(state.keymap.help_lines());
+    help_text.push(" esc - Cancel input".to_string());
 
+    let help_height = (help_text.len() as u16 + 2).min(area.height); // fit every line plus borders
     let help_area = Rect {
         x: area.width.saturating_sub(42),
-        y: area.height.saturating_sub(17),
+        y: area.height.saturating_sub(help_height + 2),
         width: (area.width / 4).max(38).min(area.width), // quarter of screen space but no less than 38
-        height: (area.height / 3).max(15).min(area.height),
+        height: help_height,
     };
 
-    let help_block = Block::default()
+    let mut help_block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::DarkGray))
         .title_top(Line::from("Help").left_aligned())
         .title_top(Line::from(format!("FPS: {}", fps)).right_aligned());
+    if state.exclusive_mode {
+        help_block = help_block.title_bottom(Line::from("exclusive").right_aligned());
+    }
+    if let Some(filter) = &state.filter {
+        help_block =
+            help_block.title_bottom(Line::from(format!("filter: {}", filter.describe())).left_aligned());
+    }
 
     let help_paragraph = Paragraph::new(help_text.join("\n"))
         .block(help_block)
@@ -507,24 +2481,34 @@ fn draw_help(frame: &mut Frame, update_rate: u64) {
 
 #[tokio::main(worker_threads = 2)]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().collect();
+    if cli::run(&args)? {
+        return Ok(());
+    }
+    let _lock = storage::Lock::acquire()?;
+    let (config, config_error) = Config::load_for_app()?;
+    // before raw mode, so a broken file or failed migration is reported on a normal terminal
+    let storage = storage::open(&config)?;
+    let persisted = storage.load()?;
     enable_raw_mode()?;
     mouse::enable_mouse()?;
+    input::enable_paste()?;
     let mut terminal = ratatui::init();
-    let mut state = State::new();
+    let mut state = State::new(config, storage);
+    if let Some(e) = config_error {
+        DebugLog::log(&e);
+        state.show_toast(e);
+    }
 
-    let mut sigterm = signal::unix::signal(signal::unix::SignalKind::terminate())?;
+    let _sigterm = signal::unix::signal(signal::unix::SignalKind::terminate())?;
     
     // try to load persisted state on startup
-    if let Ok(Some(persisted)) = State::load_from_disk() {
+    if let Some(persisted) = persisted {
         state.resume_from_persisted(persisted).await;
     } else {
         // start fresh timers if no persisted state
         for timer in &mut state.timers {
-            let time_counter = Arc::clone(&timer.timer_state);
-            let handle = tokio::spawn(async move {
-                hybrid_counter(time_counter).await;
-            });
-            timer.task_handle = Some(handle);
+            timer.start_counter();
         }
     }
 
@@ -533,9 +2517,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Duration::from_millis(state.ui_update_rate_ms),
     );
 
-    let mut last_areas: Vec<ratatui::layout::Rect> = Vec::new();
-
     'main_loop: loop {
+        // the fps keys and `:fps` only change the rate, the interval catches up here
+        if interval.period() != Duration::from_millis(state.ui_update_rate_ms) {
+            interval = time::interval_at(
+                Instant::now(),
+                Duration::from_millis(state.ui_update_rate_ms),
+            );
+        }
         interval.tick().await;
         // Snapshot timer states for rendering
         let mut time_snapshots = Vec::new();
@@ -543,21 +2532,126 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let time_guard = timer.timer_state.lock().await;
             time_snapshots.push(time_guard.clone());
         }
+        state.check_alerts(&time_snapshots).await;
+        let visible = state.visible_timers();
         terminal.draw(|frame| {
-            let areas = get_layout_areas(frame, state.timers.len());
-            last_areas = areas.clone(); // make copies of areas for mouse click selection
-            for i in 0..state.timers.len() {
-                draw_timer_box(
+            if let Some(view) = &state.shelf_view {
+                trash::draw(
                     frame,
-                    areas[i],
-                    &state.timers[i],
-                    &time_snapshots[i],
-                    i,
-                    &state,
+                    view,
+                    &state.archive,
+                    &state.trash,
+                    state.config.trash_days,
+                    state.config.time_format.as_ref(),
+                );
+                state.grid_areas.clear();
+                if let Some((message, shown_at)) = &state.toast {
+                    if shown_at.elapsed() < Duration::from_secs(4) {
+                        draw_toast(frame, message);
+                    }
+                }
+                if let Some(dialog) = &state.dialog {
+                    dialog.draw(frame);
+                }
+                return;
+            }
+            if state.show_report {
+                let report = report::build(
+                    &trash::with_archived(
+                        &persist_snapshots(&state.timers, &time_snapshots),
+                        &state.archive,
+                    ),
+                    state.report_period,
+                    state.report_grouping,
+                    state.report_offset,
+                    Time::current_unix_time(),
                 );
+                report::draw_report(frame, &report, state.config.time_format.as_ref());
+                state.grid_areas.clear(); // nothing to click on behind the report
+                if state.show_dbg {
+                    draw_debug_box(frame);
+                }
+                return;
+            }
+            let mut grid_area = frame.area();
+            let paged = visible.len() > view_capacity(state.view, grid_area);
+            if paged {
+                grid_area.height = grid_area.height.saturating_sub(1); // the page indicator
+            }
+            state.page_size = view_capacity(state.view, grid_area);
+            let start = state.page_start(&visible);
+            let page = &visible[start..(start + state.page_size).min(visible.len())];
+            let areas = view_areas(state.view, grid_area, page.len());
+            state.grid_areas = areas.clone(); // make copies of areas for mouse click selection
+            if paged {
+                draw_page_indicator(frame, start, state.page_size, visible.len(), &state.keymap);
+            }
+            match state.view {
+                View::Grid | View::Focus => {
+                    for (area, &i) in areas.iter().zip(page) {
+                        draw_timer_box(
+                            frame,
+                            *area,
+                            &state.timers[i],
+                            &time_snapshots[i],
+                            i,
+                            &state,
+                        );
+                    }
+                }
+                View::Table => draw_table(frame, grid_area, page, &time_snapshots, &state),
+                View::Sidebar => draw_sidebar(frame, grid_area, page, &time_snapshots, &state),
+            }
+            if let (Some(filter), true) = (&state.filter, visible.is_empty()) {
+                let message = format!("No timer matches {}, press f to change the filter", filter.describe());
+                frame.render_widget(Paragraph::new(message).centered(), frame.area());
             }
             if state.show_help {
-                draw_help(frame, state.ui_update_rate_ms);
+                draw_help(frame, &state);
+            }
+            if state.input_mode == Some(InputMode::Filter) {
+                draw_input_prompt(frame, "Filter (#tag, @project, empty shows all, tab completes)", &state.input);
+            }
+            if let Some((message, shown_at)) = &state.toast {
+                if shown_at.elapsed() < Duration::from_secs(4) {
+                    draw_toast(frame, message);
+                }
+            }
+            if state.input_mode == Some(InputMode::ExportPath) {
+                draw_input_prompt(frame, "Export to (.csv/.json/.ndjson/.data/.org/.ics)", &state.input);
+            }
+            if let Some(picker) = &state.picker {
+                picker.draw(frame, &state.config.templates, state.config.time_format.as_ref());
+            }
+            if let Some(palette) = &state.palette {
+                palette.draw(frame, &state.keymap);
+            }
+            if let Some(finder) = &state.finder {
+                finder.draw(frame, &state.finder_candidates(&time_snapshots));
+            }
+            if let Some(menu) = &mut state.menu {
+                menu.draw(frame);
+            }
+            if state.input_mode == Some(InputMode::Command) {
+                draw_command_line(frame, &state.input);
+            }
+            if let Some(list) = &state.session_list {
+                if let Some(timer) = state.timers.iter().find(|t| t.timer_id == list.timer_id) {
+                    list.draw(
+                        frame,
+                        &timer.sessions,
+                        timer.session_start,
+                        &timer.session_note,
+                        Time::current_unix_time(),
+                        timer.time_format(&state.config),
+                    );
+                }
+            }
+            if let Some(editor) = &state.notes_editor {
+                editor.draw(frame);
+            }
+            if let Some(dialog) = &state.dialog {
+                dialog.draw(frame);
             }
             if state.show_dbg {
                 draw_debug_box(frame);
@@ -565,6 +2659,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         })?;
         // auto-save periodically
         if state.should_save() {
+            // the app may stay open for days, trash_days has to hold then too
+            state.expire_trash();
             if let Err(e) = state.save_to_disk().await {
                 eprintln!("Warning: Failed to save state: {}", e);
             } else {
@@ -579,97 +2675,157 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         while events_this_tick < 6 && crossterm::event::poll(Duration::ZERO)? {
             events_this_tick += 1;
             match event::read()? {
-            Event::Mouse(mouse_event) => {
-                if let MouseEventKind::Down(_) = mouse_event.kind {
-                    if let Some(idx) = mouse::hit_test(mouse_event.column, mouse_event.row, &last_areas) {
-                        state.selected_timer = idx;
-                    }
+            Event::Mouse(mouse_event)
+                if state.dialog.is_none()
+                    && state.input_mode.is_none()
+                    && state.picker.is_none()
+                    && state.palette.is_none()
+                    && state.finder.is_none()
+                    && state.notes_editor.is_none()
+                    && state.session_list.is_none() =>
+            {
+                state.handle_mouse(mouse_event, &visible).await;
+            }
+            Event::Paste(text) if state.dialog.is_none() => {
+                if let Some(editor) = &mut state.notes_editor {
+                    editor.paste(&text);
+                } else if let Some(palette) = &mut state.palette {
+                    palette.query.insert(&text);
+                } else if let Some(finder) = &mut state.finder {
+                    finder.query.insert(&text);
+                } else if state.input_mode.is_some() {
+                    state.input.insert(&text);
                 }
             }
             Event::Key(key) => {
-                if state.input_mode {
-                    match key.code {
-                        KeyCode::Enter => state.set_label(),
-                        KeyCode::Esc => {
-                            state.input_mode = false;
-                            state.input_buffer.clear();
-                        }
-                        KeyCode::Backspace => {
-                            state.input_buffer.pop();
-                        }
-                        KeyCode::Char(c) => {
-                            state.input_buffer.push(c);
+                if let Some(dialog) = &state.dialog {
+                    match dialog.handle_key(key) {
+                        DialogResult::Confirmed => {
+                            if let Some(dialog) = state.dialog.take() {
+                                if state.perform(dialog.action).await {
+                                    break 'main_loop;
+                                }
+                            }
                         }
-                        _ => {}
+                        DialogResult::Cancelled => state.dialog = None,
+                        DialogResult::Pending => {}
                     }
-                } else {
+                } else if let Some(editor) = &mut state.notes_editor {
+                    match editor.handle_key(key) {
+                        EditorResult::Save => state.save_note().await,
+                        EditorResult::Cancel => state.notes_editor = None,
+                        EditorResult::Editing => {}
+                    }
+                } else if state.session_list.is_some() {
                     match key.code {
-                        KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            'confirm_loop: loop {
-                                terminal.draw(|frame| {
-                                    draw_confirmation_prompt(frame);
-                                })?;
-                                if let Event::Key(key) = event::read()? {
-                                    match key.code {
-                                        KeyCode::Char('y') => {
-                                            state.save_to_disk().await; // save state and quit
-                                            break 'main_loop;
-                                        }
-                                        KeyCode::Char('n') => break 'confirm_loop,
-                                        _ => continue,
-                                    }
+                        KeyCode::Up => state.move_session_selection(-1),
+                        KeyCode::Down => state.move_session_selection(1),
+                        KeyCode::Enter => state.edit_session_note(),
+                        KeyCode::Esc => state.session_list = None,
+                        _ => {}
+                    }
+                } else if let Some(mut finder) = state.finder.take() {
+                    let candidates = state.finder_candidates(&time_snapshots);
+                    match finder.handle_key(key, &candidates) {
+                        FinderResult::Select(idx) => {
+                            state.reveal(idx);
+                            state.select_timer(idx).await;
+                        }
+                        FinderResult::Close => {}
+                        FinderResult::Open => state.finder = Some(finder),
+                    }
+                } else if let Some(palette) = &mut state.palette {
+                    match palette.handle_key(key) {
+                        PaletteResult::Run(spec) => {
+                            state.palette = None;
+                            if spec.takes_arguments() {
+                                // let the arguments be typed on the command line
+                                state.start_input(InputMode::Command);
+                                state.input.set(&format!("{} ", spec.name));
+                            } else {
+                                let timers = trash::with_archived(
+                                    &persist_snapshots(&state.timers, &time_snapshots),
+                                    &state.archive,
+                                );
+                                if state.run_command(spec.name, &timers).await {
+                                    break 'main_loop;
                                 }
                             }
                         }
-                        KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            if state.timers.len() < 24 {
-                                state.add_timer();
-                                let idx = state.timers.len() - 1;
-                                let timer = &mut state.timers[idx];
-                                let counter_time = Arc::clone(&timer.timer_state);
-                                let handle = tokio::spawn(async move {
-                                    hybrid_counter(counter_time).await;
-                                });
-                                timer.task_handle = Some(handle);
-                            }
+                        PaletteResult::Close => state.palette = None,
+                        PaletteResult::Open => {}
+                    }
+                } else if let Some(menu) = &mut state.menu {
+                    match menu.handle_key(key) {
+                        MenuResult::Chosen(choice) => state.choose_from_menu(choice).await,
+                        MenuResult::Close => state.menu = None,
+                        MenuResult::Open => {}
+                    }
+                } else if let Some(picker) = &mut state.picker {
+                    match key.code {
+                        KeyCode::Up => picker.move_selection(-1, &state.config.templates),
+                        KeyCode::Down => picker.move_selection(1, &state.config.templates),
+                        KeyCode::Enter => {
+                            let template = picker.chosen(&state.config.templates).cloned();
+                            state.picker = None;
+                            state.add_timer(template).await;
                         }
-                        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            if state.timers.len() > 1 {
-                                state.remove_timer();
+                        KeyCode::Esc => state.picker = None,
+                        _ => {}
+                    }
+                } else if state.input_mode.is_some() {
+                    match key.code {
+                        KeyCode::Enter => {
+                            let timers = trash::with_archived(
+                                &persist_snapshots(&state.timers, &time_snapshots),
+                                &state.archive,
+                            );
+                            if state.submit_input(&timers).await {
+                                break 'main_loop;
                             }
                         }
-                        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            state.reset_timer().await;
-                        }
-                        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                            state.toggle_debug();
+                        KeyCode::Esc => state.cancel_input(),
+                        KeyCode::Tab => state.complete_input(),
+                        KeyCode::Up | KeyCode::Down if state.input_mode == Some(InputMode::Command) => {
+                            let delta = if key.code == KeyCode::Up { -1 } else { 1 };
+                            let line = state.command_history.step(delta).unwrap_or_default().to_string();
+                            state.input.set(&line);
                         }
-                        KeyCode::Char('h') => state.toggle_help(),
-                        KeyCode::Char('l') => {
-                            state.input_mode = true;
-                            state.input_buffer.clear();
+                        KeyCode::Backspace
+                            if state.input_mode == Some(InputMode::Command) && state.input.text().is_empty() =>
+                        {
+                            state.cancel_input();
                         }
-                        KeyCode::Up => {
-                            if state.ui_update_rate_ms > 10 {
-                                state.ui_update_rate_ms = state.ui_update_rate_ms.saturating_sub(5);
-                                interval = time::interval_at(
-                                    Instant::now(),
-                                    Duration::from_millis(state.ui_update_rate_ms),
-                                );
-                            }
+                        _ => {
+                            state.input.handle_key(key);
                         }
-                        KeyCode::Down => {
-                            if state.ui_update_rate_ms < 100 {
-                                state.ui_update_rate_ms = state.ui_update_rate_ms.saturating_add(5);
-                                interval = time::interval_at(
-                                    Instant::now(),
-                                    Duration::from_millis(state.ui_update_rate_ms),
-                                );
-                            }
+                    }
+                } else if let Some(view) = &mut state.shelf_view {
+                    match key.code {
+                        KeyCode::Tab => view.switch_shelf(),
+                        KeyCode::Up => state.move_shelf_selection(-1),
+                        KeyCode::Down => state.move_shelf_selection(1),
+                        KeyCode::Enter => state.restore_from_shelf(),
+                        KeyCode::Delete | KeyCode::Char('x') => {
+                            state.request(ConfirmAction::Purge).await;
                         }
-                        KeyCode::Tab => state.next_timer(),
+                        KeyCode::Char('b') | KeyCode::Esc => state.toggle_shelf_view(),
                         _ => {}
                     }
+                } else if state.show_report {
+                    match key.code {
+                        KeyCode::Char('d') => state.set_report_period(Period::Day),
+                        KeyCode::Char('w') => state.set_report_period(Period::Week),
+                        KeyCode::Char('g') => state.report_grouping = state.report_grouping.next(),
+                        KeyCode::Left => state.report_offset -= 1,
+                        KeyCode::Right if state.report_offset < 0 => state.report_offset += 1,
+                        KeyCode::Char('s') | KeyCode::Esc => state.toggle_report(),
+                        _ => {}
+                    }
+                } else if let Some(action) = state.keymap.action(key) {
+                    if state.run_action(action).await {
+                        break 'main_loop;
+                    }
                 }
             }
             _ => {}
@@ -677,8 +2833,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
     // save on exit
-    let _ = state.save_to_disk();
+    let _ = state.save_to_disk().await;
     let _ = mouse::disable_mouse();
+    let _ = input::disable_paste();
     ratatui::restore();
     Ok(())
 }
\ No newline at end of file
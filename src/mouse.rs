@@ -1,23 +1,206 @@
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::event::{
+    DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEvent, MouseButton, MouseEvent,
+    MouseEventKind,
+};
 use crossterm::execute;
-use ratatui::layout::Rect;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState},
+    Frame,
+};
 use std::io::stdout;
- 
+use std::str::FromStr;
+
+use crate::keymap::{Action, Keymap};
+
+// what the context menu can offer, colors go in a second list
+pub const COLORS: [&str; 8] = [
+    "red", "green", "yellow", "blue", "magenta", "cyan", "white", "gray",
+];
+
 pub fn enable_mouse() -> Result<(), Box<dyn std::error::Error>> {
     execute!(stdout(), EnableMouseCapture)?;
     Ok(())
 }
- 
+
 pub fn disable_mouse() -> Result<(), Box<dyn std::error::Error>> {
     execute!(stdout(), DisableMouseCapture)?;
     Ok(())
 }
- 
+
 pub fn hit_test(col: u16, row: u16, areas: &[Rect]) -> Option<usize> {
     areas.iter().position(|rect| {
-        col >= rect.x
-            && col < rect.x + rect.width
-            && row >= rect.y
-            && row < rect.y + rect.height
+        col >= rect.x && col < rect.x + rect.width && row >= rect.y && row < rect.y + rect.height
     })
 }
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MenuChoice {
+    Run(Action),
+    Colors,
+    Color(Option<&'static str>), // None goes back to the default color
+}
+
+pub enum MenuResult {
+    Chosen(MenuChoice),
+    Close,
+    Open,
+}
+
+// right click on a timer, a small list next to the pointer
+pub struct ContextMenu {
+    pub timer: usize,
+    at: (u16, u16),
+    items: Vec<(String, String, MenuChoice)>, // text, key hint, choice
+    selected: usize,
+    area: Rect, // where it was last drawn, for clicks
+}
+
+impl ContextMenu {
+    pub fn new(timer: usize, column: u16, row: u16, running: bool, keymap: &Keymap) -> Self {
+        let pause = if running { "Pause" } else { "Resume" };
+        let entry = |text: &str, action: Action| {
+            let hint = keymap
+                .keys(action)
+                .first()
+                .map(|key| key.to_string())
+                .unwrap_or_default();
+            (text.to_string(), hint, MenuChoice::Run(action))
+        };
+        Self {
+            timer,
+            at: (column, row),
+            items: vec![
+                entry(pause, Action::TogglePause),
+                entry("Reset", Action::ResetTimer),
+                entry("Label", Action::EditLabel),
+                ("Color".to_string(), "›".to_string(), MenuChoice::Colors),
+                entry("Delete", Action::DeleteTimer),
+            ],
+            selected: 0,
+            area: Rect::default(),
+        }
+    }
+
+    // swap the entries for the color list, in the same spot
+    pub fn show_colors(&mut self) {
+        self.items = std::iter::once((
+            "default".to_string(),
+            String::new(),
+            MenuChoice::Color(None),
+        ))
+        .chain(COLORS.iter().map(|name| {
+            (
+                name.to_string(),
+                String::new(),
+                MenuChoice::Color(Some(name)),
+            )
+        }))
+        .collect();
+        self.selected = 0;
+    }
+
+    fn step(&mut self, delta: isize) {
+        self.selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(self.items.len() - 1);
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> MenuResult {
+        match key.code {
+            KeyCode::Esc => return MenuResult::Close,
+            KeyCode::Enter | KeyCode::Right => {
+                return MenuResult::Chosen(self.items[self.selected].2)
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.step(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.step(1),
+            _ => {}
+        }
+        MenuResult::Open
+    }
+
+    // a click on an entry picks it, a click anywhere else closes the menu
+    pub fn handle_mouse(&mut self, mouse_event: MouseEvent) -> MenuResult {
+        let entry = hit_test(mouse_event.column, mouse_event.row, &[self.area])
+            .and_then(|_| mouse_event.row.checked_sub(self.area.y + 1))
+            .map(usize::from)
+            .filter(|row| *row < self.items.len());
+        match mouse_event.kind {
+            MouseEventKind::Moved => {
+                if let Some(row) = entry {
+                    self.selected = row;
+                }
+            }
+            MouseEventKind::ScrollUp => self.step(-1),
+            MouseEventKind::ScrollDown => self.step(1),
+            MouseEventKind::Down(MouseButton::Left) => {
+                return match entry {
+                    Some(row) => MenuResult::Chosen(self.items[row].2),
+                    None if hit_test(mouse_event.column, mouse_event.row, &[self.area])
+                        .is_some() =>
+                    {
+                        MenuResult::Open
+                    }
+                    None => MenuResult::Close,
+                }
+            }
+            MouseEventKind::Down(_) => return MenuResult::Close,
+            _ => {}
+        }
+        MenuResult::Open
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame) {
+        let screen = frame.area();
+        let text_width = self
+            .items
+            .iter()
+            .map(|(text, hint, _)| text.chars().count() + hint.chars().count() + 2)
+            .max()
+            .unwrap_or(0) as u16;
+        let width = (text_width + 2).min(screen.width);
+        let height = (self.items.len() as u16 + 2).min(screen.height);
+        // open down and to the right of the pointer, flipped when it would leave the screen
+        let x = if self.at.0 + width > screen.width {
+            self.at.0.saturating_sub(width)
+        } else {
+            self.at.0
+        };
+        let y = if self.at.1 + height > screen.height {
+            self.at.1.saturating_sub(height)
+        } else {
+            self.at.1
+        };
+        self.area = Rect::new(x, y, width, height);
+
+        let inner_width = width.saturating_sub(2) as usize;
+        let items: Vec<ListItem> = self
+            .items
+            .iter()
+            .map(|(text, hint, choice)| {
+                let pad = inner_width.saturating_sub(text.chars().count() + hint.chars().count());
+                let style = match choice {
+                    MenuChoice::Color(Some(name)) => Color::from_str(name)
+                        .map(|color| Style::default().fg(color))
+                        .unwrap_or_default(),
+                    _ => Style::default(),
+                };
+                ListItem::new(format!("{}{}{}", text, " ".repeat(pad), hint)).style(style)
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Green)),
+            )
+            .style(Style::default().fg(Color::Gray).bg(Color::Black))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut list_state = ListState::default();
+        list_state.select(Some(self.selected));
+        frame.render_widget(Clear, self.area);
+        frame.render_stateful_widget(list, self.area, &mut list_state);
+    }
+}
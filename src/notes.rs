@@ -0,0 +1,286 @@
+use chrono::{DateTime, Local};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Position, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Padding, Paragraph},
+    Frame,
+};
+
+use crate::report::duration_text;
+use crate::session::Session;
+use crate::timefmt::TimeFormat;
+
+// what a note belongs to, timers are found by id so the grid can change underneath
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NoteTarget {
+    Timer(usize),
+    Session(usize, Option<usize>), // timer id and index into its sessions, None is the running one
+}
+
+pub enum EditorResult {
+    Save,
+    Cancel,
+    Editing,
+}
+
+// multi-line text area in a popup, ctrl+s keeps the text and esc throws it away
+pub struct NotesEditor {
+    pub target: NoteTarget,
+    title: String,
+    lines: Vec<String>,
+    row: usize,
+    col: usize, // in chars
+}
+
+fn byte_index(line: &str, col: usize) -> usize {
+    line.char_indices().nth(col).map_or(line.len(), |(i, _)| i)
+}
+
+impl NotesEditor {
+    pub fn new(target: NoteTarget, title: String, text: &str) -> Self {
+        let lines: Vec<String> = text.split('\n').map(String::from).collect();
+        let row = lines.len() - 1;
+        let col = lines[row].chars().count();
+        Self {
+            target,
+            title,
+            lines,
+            row,
+            col,
+        }
+    }
+
+    // trailing blank lines are dropped so an emptied editor clears the note
+    pub fn text(&self) -> String {
+        self.lines.join("\n").trim_end().to_string()
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    fn insert(&mut self, c: char) {
+        let at = byte_index(&self.lines[self.row], self.col);
+        self.lines[self.row].insert(at, c);
+        self.col += 1;
+    }
+
+    fn newline(&mut self) {
+        let at = byte_index(&self.lines[self.row], self.col);
+        let rest = self.lines[self.row].split_off(at);
+        self.lines.insert(self.row + 1, rest);
+        self.row += 1;
+        self.col = 0;
+    }
+
+    fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let at = byte_index(&self.lines[self.row], self.col);
+            self.lines[self.row].remove(at);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.col < self.line_len() {
+            let at = byte_index(&self.lines[self.row], self.col);
+            self.lines[self.row].remove(at);
+        } else if self.row + 1 < self.lines.len() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        }
+    }
+
+    // pasted text keeps its line breaks, tabs and other control characters are dropped
+    pub fn paste(&mut self, text: &str) {
+        for c in text.replace("\r\n", "\n").chars() {
+            match c {
+                '\n' | '\r' => self.newline(),
+                c if c.is_control() => {}
+                c => self.insert(c),
+            }
+        }
+    }
+
+    fn move_to_row(&mut self, row: usize) {
+        self.row = row;
+        self.col = self.col.min(self.line_len());
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> EditorResult {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('s') if ctrl => return EditorResult::Save,
+            KeyCode::Esc => return EditorResult::Cancel,
+            KeyCode::Char(c) if !ctrl => self.insert(c),
+            KeyCode::Enter => self.newline(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left if self.col > 0 => self.col -= 1,
+            KeyCode::Left if self.row > 0 => {
+                self.row -= 1;
+                self.col = self.line_len();
+            }
+            KeyCode::Right if self.col < self.line_len() => self.col += 1,
+            KeyCode::Right if self.row + 1 < self.lines.len() => {
+                self.row += 1;
+                self.col = 0;
+            }
+            KeyCode::Up if self.row > 0 => self.move_to_row(self.row - 1),
+            KeyCode::Down if self.row + 1 < self.lines.len() => self.move_to_row(self.row + 1),
+            KeyCode::Home => self.col = 0,
+            KeyCode::End => self.col = self.line_len(),
+            _ => {}
+        }
+        EditorResult::Editing
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let width = (area.width * 2 / 3).max(40).min(area.width);
+        let height = (area.height / 2).max(8).min(area.height);
+        let editor_area = Rect::new(
+            (area.width - width) / 2,
+            (area.height - height) / 2,
+            width,
+            height,
+        );
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Green))
+            .title(format!(" {} ", self.title))
+            .title_bottom(Line::from(" ctrl+s save · esc cancel ").right_aligned())
+            .padding(Padding::horizontal(1));
+        let inner = block.inner(editor_area);
+        // keep the cursor row in view
+        let scroll = (self.row as u16).saturating_sub(inner.height.saturating_sub(1));
+        let lines: Vec<Line> = self.lines.iter().map(|l| Line::from(l.as_str())).collect();
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .scroll((scroll, 0))
+            .style(Style::default().fg(Color::Gray).bg(Color::Black));
+        frame.render_widget(Clear, editor_area);
+        frame.render_widget(paragraph, editor_area);
+        frame.set_cursor_position(Position::new(
+            inner.x + (self.col as u16).min(inner.width.saturating_sub(1)),
+            inner.y + self.row as u16 - scroll,
+        ));
+    }
+}
+
+// the sessions of one timer, newest first, to pick the one a note is for
+pub struct SessionList {
+    pub timer_id: usize,
+    pub selected: usize,
+}
+
+// rows of the session list, the running interval (if any) comes first
+pub fn session_rows(sessions: &[Session], open_start: Option<u64>) -> Vec<Option<usize>> {
+    let mut rows: Vec<Option<usize>> = Vec::new();
+    if open_start.is_some() {
+        rows.push(None);
+    }
+    let mut closed: Vec<usize> = (0..sessions.len()).collect();
+    closed.sort_by_key(|i| std::cmp::Reverse(sessions[*i].start));
+    rows.extend(closed.into_iter().map(Some));
+    rows
+}
+
+impl SessionList {
+    pub fn new(timer_id: usize) -> Self {
+        Self {
+            timer_id,
+            selected: 0,
+        }
+    }
+
+    pub fn move_selection(&mut self, delta: isize, len: usize) {
+        self.selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+
+    pub fn draw(
+        &self,
+        frame: &mut Frame,
+        sessions: &[Session],
+        open_start: Option<u64>,
+        open_note: &str,
+        now: u64,
+        format: Option<&TimeFormat>,
+    ) {
+        let rows = session_rows(sessions, open_start);
+        let items: Vec<ListItem> = rows
+            .iter()
+            .map(|row| {
+                let (start, length, note) = match row {
+                    None => {
+                        let start = open_start.unwrap_or(now);
+                        let running = format!(
+                            "{} (running)",
+                            duration_text(now.saturating_sub(start), format)
+                        );
+                        (start, running, open_note)
+                    }
+                    Some(i) => {
+                        let session = &sessions[*i];
+                        let length = if session.is_adjustment() {
+                            format!("adjusted {:+}s", session.adjustment)
+                        } else {
+                            duration_text(session.end.saturating_sub(session.start), format)
+                        };
+                        (session.start, length, session.note.as_str())
+                    }
+                };
+                let first_line = note.lines().next().unwrap_or_default();
+                let started = DateTime::from_timestamp(start as i64, 0)
+                    .unwrap_or_default()
+                    .with_timezone(&Local);
+                ListItem::new(format!(
+                    "{}  {:<22} {}",
+                    started.format("%Y-%m-%d %H:%M"),
+                    length,
+                    first_line
+                ))
+            })
+            .collect();
+        let empty = items.is_empty();
+        let list = List::new(if empty {
+            vec![ListItem::new("nothing recorded yet")]
+        } else {
+            items
+        })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Green))
+                .title(" Sessions ")
+                .title_bottom(Line::from(" enter edit note · esc close ").right_aligned())
+                .padding(Padding::horizontal(1)),
+        )
+        .style(Style::default().fg(Color::Gray).bg(Color::Black))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut list_state = ListState::default();
+        list_state.select((!empty).then_some(self.selected));
+
+        let area = frame.area();
+        let width = (area.width * 2 / 3).max(40).min(area.width);
+        let height = (area.height * 2 / 3).max(8).min(area.height);
+        let list_area = Rect::new(
+            (area.width - width) / 2,
+            (area.height - height) / 2,
+            width,
+            height,
+        );
+        frame.render_widget(Clear, list_area);
+        frame.render_stateful_widget(list, list_area, &mut list_state);
+    }
+}
@@ -0,0 +1,358 @@
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveTime};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Bar, BarChart, Block, Borders, Padding, Paragraph},
+    Frame,
+};
+use std::collections::BTreeMap;
+
+use crate::session;
+use crate::timefmt::TimeFormat;
+use crate::PersistedTimer;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Period {
+    Day,
+    Week,
+}
+
+// what the rows of a report are, a timer with several tags counts towards each of them
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Grouping {
+    Label,
+    Project,
+    Tag,
+}
+
+impl Grouping {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "label" => Some(Self::Label),
+            "project" => Some(Self::Project),
+            "tag" | "tags" => Some(Self::Tag),
+            _ => None,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Label => Self::Project,
+            Self::Project => Self::Tag,
+            Self::Tag => Self::Label,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Label => "label",
+            Self::Project => "project",
+            Self::Tag => "tag",
+        }
+    }
+
+    fn keys(self, timer: &PersistedTimer) -> Vec<String> {
+        match self {
+            Self::Label => vec![timer_label(timer)],
+            Self::Project => vec![timer
+                .project
+                .clone()
+                .unwrap_or_else(|| "no project".to_string())],
+            Self::Tag if timer.tags.is_empty() => vec!["untagged".to_string()],
+            Self::Tag => timer.tags.iter().map(|tag| format!("#{}", tag)).collect(),
+        }
+    }
+}
+
+pub struct Report {
+    pub period: Period,
+    pub grouping: Grouping,
+    pub first_day: NaiveDate,
+    pub groups: Vec<(String, u64)>, // largest first
+    pub by_day: Vec<(NaiveDate, u64)>,
+    pub total: u64,
+    pub notes: Vec<(String, String)>, // heading and note, timer notes first then sessions by time
+}
+
+pub fn timer_label(timer: &PersistedTimer) -> String {
+    timer
+        .label
+        .clone()
+        .unwrap_or_else(|| format!("unlabeled #{}", timer.timer_id))
+}
+
+pub fn local_date(timestamp: u64) -> NaiveDate {
+    DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .with_timezone(&Local)
+        .date_naive()
+}
+
+pub fn local_midnight(date: NaiveDate) -> u64 {
+    let naive = date.and_time(NaiveTime::MIN);
+    // midnight can fall into a DST gap, utc is close enough then
+    naive
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|dt| dt.timestamp())
+        .unwrap_or_else(|| naive.and_utc().timestamp())
+        .max(0) as u64
+}
+
+// time_format from config.toml when there is one, 1h:02m:03s otherwise
+pub fn duration_text(seconds: u64, format: Option<&TimeFormat>) -> String {
+    format.map_or_else(|| format_duration(seconds), |format| format.format(seconds))
+}
+
+pub fn format_duration(seconds: u64) -> String {
+    format!(
+        "{}h:{:02}m:{:02}s",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
+
+// offset shifts by whole periods, -1 is yesterday / last week
+fn period_range(period: Period, offset: i64, today: NaiveDate) -> (NaiveDate, u64) {
+    let (anchor, days) = match period {
+        Period::Day => (today, 1),
+        Period::Week => (
+            today - Days::new(today.weekday().num_days_from_monday() as u64),
+            7,
+        ),
+    };
+    let shift = offset.unsigned_abs() * days;
+    let first_day = if offset < 0 {
+        anchor - Days::new(shift)
+    } else {
+        anchor + Days::new(shift)
+    };
+    (first_day, days)
+}
+
+pub fn build(
+    timers: &[PersistedTimer],
+    period: Period,
+    grouping: Grouping,
+    offset: i64,
+    now: u64,
+) -> Report {
+    let (first_day, days) = period_range(period, offset, local_date(now));
+    let intervals: Vec<(Vec<String>, Vec<session::Session>)> = timers
+        .iter()
+        .map(|timer| {
+            (
+                grouping.keys(timer),
+                session::with_open(
+                    &timer.sessions,
+                    timer.session_start,
+                    &timer.session_note,
+                    now,
+                ),
+            )
+        })
+        .collect();
+
+    // signed until the end, negative adjustments can take time back
+    let mut groups: BTreeMap<String, i64> = BTreeMap::new();
+    let mut by_day = Vec::new();
+    for i in 0..days {
+        let day = first_day + Days::new(i);
+        let day_start = local_midnight(day);
+        let day_end = local_midnight(day + Days::new(1));
+        let mut day_total = 0;
+        for (keys, sessions) in &intervals {
+            let seconds: i64 = sessions
+                .iter()
+                .map(|s| session::seconds_in(s, day_start, day_end))
+                .sum();
+            if seconds != 0 {
+                for key in keys {
+                    *groups.entry(key.clone()).or_default() += seconds;
+                }
+                day_total += seconds;
+            }
+        }
+        by_day.push((day, day_total.max(0) as u64));
+    }
+
+    let mut groups: Vec<(String, u64)> = groups
+        .into_iter()
+        .filter(|(_, seconds)| *seconds > 0)
+        .map(|(key, seconds)| (key, seconds as u64))
+        .collect();
+    groups.sort_by_key(|(_, seconds)| std::cmp::Reverse(*seconds));
+    let total = by_day.iter().map(|(_, seconds)| seconds).sum();
+    Report {
+        period,
+        grouping,
+        first_day,
+        groups,
+        by_day,
+        total,
+        notes: period_notes(timers, &intervals, first_day, days),
+    }
+}
+
+// notes of the timers that were used in the period and of the sessions inside it
+fn period_notes(
+    timers: &[PersistedTimer],
+    intervals: &[(Vec<String>, Vec<session::Session>)],
+    first_day: NaiveDate,
+    days: u64,
+) -> Vec<(String, String)> {
+    let from = local_midnight(first_day);
+    let to = local_midnight(first_day + Days::new(days));
+    let mut notes = Vec::new();
+    let mut session_notes = Vec::new();
+    for (timer, (_, sessions)) in timers.iter().zip(intervals) {
+        let in_period: Vec<&session::Session> = sessions
+            .iter()
+            .filter(|s| session::seconds_in(s, from, to) != 0)
+            .collect();
+        if !in_period.is_empty() && !timer.notes.is_empty() {
+            notes.push((timer_label(timer), timer.notes.clone()));
+        }
+        for s in in_period.into_iter().filter(|s| !s.note.is_empty()) {
+            session_notes.push((s.start, timer_label(timer), s.note.clone()));
+        }
+    }
+    session_notes.sort_by_key(|(start, _, _)| *start);
+    notes.extend(session_notes.into_iter().map(|(start, label, note)| {
+        let started = DateTime::from_timestamp(start as i64, 0)
+            .unwrap_or_default()
+            .with_timezone(&Local);
+        (
+            format!("{} {}", started.format("%a %m-%d %H:%M"), label),
+            note,
+        )
+    }));
+    notes
+}
+
+fn percent(seconds: u64, total: u64) -> u64 {
+    (seconds * 100).checked_div(total).unwrap_or(0)
+}
+
+fn title(report: &Report) -> String {
+    let period = match report.period {
+        Period::Day => format!("Day {}", report.first_day.format("%a %Y-%m-%d")),
+        Period::Week => format!(
+            "Week {} (from {})",
+            report.first_day.iso_week().week(),
+            report.first_day.format("%Y-%m-%d")
+        ),
+    };
+    format!("{}, by {}", period, report.grouping.name())
+}
+
+fn summary_lines(report: &Report, format: Option<&TimeFormat>) -> Vec<String> {
+    let width = report
+        .groups
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let mut lines = Vec::new();
+    if report.groups.is_empty() {
+        lines.push("nothing tracked".to_string());
+    }
+    for (label, seconds) in &report.groups {
+        lines.push(format!(
+            "{:<width$}  {:>12}  {:>3}%",
+            label,
+            duration_text(*seconds, format),
+            percent(*seconds, report.total),
+        ));
+    }
+    lines.push(format!(
+        "{:<width$}  {:>12}",
+        "total",
+        duration_text(report.total, format)
+    ));
+    if report.period == Period::Week {
+        lines.push(String::new());
+        for (day, seconds) in &report.by_day {
+            lines.push(format!(
+                "{}  {:>12}",
+                day.format("%a %m-%d"),
+                duration_text(*seconds, format)
+            ));
+        }
+    }
+    if !report.notes.is_empty() {
+        lines.push(String::new());
+        lines.push("notes".to_string());
+        for (heading, note) in &report.notes {
+            let mut note_lines = note.lines();
+            lines.push(format!(
+                "{}: {}",
+                heading,
+                note_lines.next().unwrap_or_default()
+            ));
+            lines.extend(note_lines.map(|line| format!("    {}", line)));
+        }
+    }
+    lines
+}
+
+// plain text version for `stopwatch report`
+pub fn render_text(report: &Report, format: Option<&TimeFormat>) -> String {
+    let mut out = format!("{}\n", title(report));
+    let max = report.groups.first().map(|(_, s)| *s).unwrap_or(0);
+    for (i, line) in summary_lines(report, format).into_iter().enumerate() {
+        let bar = match report.groups.get(i) {
+            Some((_, seconds)) if max > 0 => "█".repeat((seconds * 20 / max) as usize),
+            _ => String::new(),
+        };
+        out.push_str(format!("  {}  {}", line, bar).trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+pub fn draw_report(frame: &mut Frame, report: &Report, format: Option<&TimeFormat>) {
+    let area = frame.area();
+    let report_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Green))
+        .title(format!(" Report: {} ", title(report)))
+        .title_bottom(
+            Line::from(" d day · w week · g group · ←/→ previous/next · esc close ")
+                .right_aligned(),
+        )
+        .padding(Padding::uniform(1));
+    let inner = report_block.inner(area);
+    frame.render_widget(report_block, area);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(inner);
+
+    let summary = Paragraph::new(summary_lines(report, format).join("\n"))
+        .style(Style::default().fg(Color::Gray));
+    frame.render_widget(summary, columns[0]);
+
+    let bars: Vec<Bar> = report
+        .groups
+        .iter()
+        .map(|(label, seconds)| {
+            Bar::with_label(label.clone(), *seconds).text_value(format!(
+                "{} ({}%)",
+                duration_text(*seconds, format),
+                percent(*seconds, report.total)
+            ))
+        })
+        .collect();
+    let chart = BarChart::horizontal(bars)
+        .bar_width(1)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(Color::Green))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Green))
+        .label_style(Style::default().fg(Color::Gray));
+    frame.render_widget(chart, columns[1]);
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+// one stretch of wall-clock time a timer was running for, in UNIX seconds
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Session {
+    pub start: u64,
+    pub end: u64,
+    // manual corrections are zero-length entries at the time they were made
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub adjustment: i64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub note: String,
+}
+
+fn is_zero(value: &i64) -> bool {
+    *value == 0
+}
+
+impl Session {
+    pub fn new(start: u64, end: u64) -> Self {
+        Self {
+            start,
+            end,
+            adjustment: 0,
+            note: String::new(),
+        }
+    }
+
+    pub fn adjustment(at: u64, seconds: i64) -> Self {
+        Self {
+            start: at,
+            end: at,
+            adjustment: seconds,
+            note: String::new(),
+        }
+    }
+
+    pub fn is_adjustment(&self) -> bool {
+        self.adjustment != 0
+    }
+
+    // same stretch of time, whatever notes are attached
+    pub fn same_interval(&self, other: &Session) -> bool {
+        self.start == other.start && self.end == other.end && self.adjustment == other.adjustment
+    }
+}
+
+// close the open interval at `now` and hand it the note written while it ran,
+// zero-length ones aren't worth keeping
+pub fn close_session(
+    sessions: &mut Vec<Session>,
+    open_start: &mut Option<u64>,
+    open_note: &mut String,
+    now: u64,
+) {
+    if let Some(start) = open_start.take() {
+        if now > start {
+            sessions.push(Session {
+                note: std::mem::take(open_note),
+                ..Session::new(start, now)
+            });
+        }
+    }
+}
+
+// recorded intervals plus the one still running, cut off at `now`
+pub fn with_open(
+    sessions: &[Session],
+    open_start: Option<u64>,
+    open_note: &str,
+    now: u64,
+) -> Vec<Session> {
+    let mut all = sessions.to_vec();
+    if let Some(start) = open_start {
+        if now > start {
+            all.push(Session {
+                note: open_note.to_string(),
+                ..Session::new(start, now)
+            });
+        }
+    }
+    all
+}
+
+// seconds of `session` that fall inside [from, to)
+pub fn overlap(session: &Session, from: u64, to: u64) -> u64 {
+    session.end.min(to).saturating_sub(session.start.max(from))
+}
+
+// what the session adds to [from, to), adjustments count where they were made
+pub fn seconds_in(session: &Session, from: u64, to: u64) -> i64 {
+    let adjustment = if (from..to).contains(&session.start) {
+        session.adjustment
+    } else {
+        0
+    };
+    overlap(session, from, to) as i64 + adjustment
+}
@@ -0,0 +1,564 @@
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use crate::config::{Config, StorageKind};
+use crate::session::Session;
+use crate::template::Mode;
+use crate::trash::TrashedTimer;
+use crate::view::View;
+use crate::{PersistedState, PersistedTimer, SwitchEvent};
+
+pub trait Storage {
+    fn load(&self) -> Result<Option<PersistedState>, Box<dyn std::error::Error>>;
+    fn save(&self, state: &PersistedState) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+// everything lives next to the executable
+pub fn data_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let exe_path = env::current_exe()?;
+    let exe_dir = exe_path
+        .parent()
+        .unwrap_or_else(|| std::path::Path::new("."));
+    Ok(exe_dir.to_path_buf())
+}
+
+// held by the app while it runs, the cli refuses to write while it exists because the app
+// would overwrite the change on its next save
+pub struct Lock {
+    _file: fs::File, // the os lets go of the lock with the file, after a crash too
+}
+
+impl Lock {
+    pub fn acquire() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = data_dir()?.join("stopwatch.lock");
+        let mut file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(fs::TryLockError::WouldBlock) => {
+                // the pid is only for the message, the lock itself is what counts
+                let mut pid = String::new();
+                let _ = file.read_to_string(&mut pid);
+                return Err(match pid.trim() {
+                    "" => "stopwatch is already running".into(),
+                    pid => format!("stopwatch is already running (pid {})", pid).into(),
+                });
+            }
+            Err(fs::TryLockError::Error(e)) => return Err(e.into()),
+        }
+        file.set_len(0)?;
+        file.write_all(std::process::id().to_string().as_bytes())?;
+        Ok(Self { _file: file })
+    }
+}
+
+pub fn open(config: &Config) -> Result<Box<dyn Storage>, Box<dyn std::error::Error>> {
+    let dir = data_dir()?;
+    let toml = TomlStorage {
+        path: dir.join("timers.toml"),
+    };
+    Ok(match config.storage {
+        StorageKind::Toml => Box::new(toml),
+        StorageKind::Sqlite => Box::new(SqliteStorage {
+            path: dir.join("timers.sqlite3"),
+            fallback: toml,
+            stored: RefCell::new(None),
+        }),
+    })
+}
+
+pub struct TomlStorage {
+    path: PathBuf,
+}
+
+impl Storage for TomlStorage {
+    fn load(&self) -> Result<Option<PersistedState>, Box<dyn std::error::Error>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&self.path)?;
+        let state: PersistedState = toml::from_str(&contents)?;
+        Ok(Some(state))
+    }
+
+    fn save(&self, state: &PersistedState) -> Result<(), Box<dyn std::error::Error>> {
+        let toml_string = toml::to_string_pretty(state)?;
+        fs::write(&self.path, toml_string)?;
+        Ok(())
+    }
+}
+
+pub struct SqliteStorage {
+    path: PathBuf,
+    fallback: TomlStorage, // picked up once when switching over from the toml file
+    stored: RefCell<Option<HashMap<usize, Stored>>>, // None until the first load or save
+}
+
+// a timer's tags and sessions as they are in the database, so a save only writes what changed
+#[derive(Default)]
+struct Stored {
+    tags: Vec<String>,
+    sessions: Vec<Session>,
+}
+
+// applied in order, PRAGMA user_version tracks how many already ran
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value INTEGER NOT NULL
+    );
+    CREATE TABLE timers (
+        timer_id INTEGER PRIMARY KEY,
+        position INTEGER NOT NULL,
+        label TEXT,
+        elapsed_seconds INTEGER NOT NULL,
+        last_wall_clock INTEGER NOT NULL,
+        running INTEGER NOT NULL,
+        session_start INTEGER
+    );
+    CREATE TABLE sessions (
+        timer_id INTEGER NOT NULL,
+        start INTEGER NOT NULL,
+        end INTEGER NOT NULL
+    );
+    CREATE INDEX sessions_by_timer ON sessions (timer_id, start);
+    CREATE TABLE events (
+        timestamp INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        from_timer INTEGER,
+        to_timer INTEGER
+    );",
+    // 2: manual time adjustments
+    "ALTER TABLE sessions ADD COLUMN adjustment INTEGER NOT NULL DEFAULT 0;",
+    // 3: archive and trash, 'active' | 'archived' | 'trashed'
+    "ALTER TABLE timers ADD COLUMN status TEXT NOT NULL DEFAULT 'active';
+    ALTER TABLE timers ADD COLUMN deleted_at INTEGER;",
+    // 4: template settings
+    "ALTER TABLE timers ADD COLUMN mode TEXT NOT NULL DEFAULT 'count-up';
+    ALTER TABLE timers ADD COLUMN target INTEGER;
+    ALTER TABLE timers ADD COLUMN color TEXT;
+    CREATE TABLE tags (
+        timer_id INTEGER NOT NULL,
+        tag TEXT NOT NULL
+    );
+    CREATE INDEX tags_by_timer ON tags (timer_id);",
+    // 5: projects
+    "ALTER TABLE timers ADD COLUMN project TEXT;",
+    // 6: notes
+    "ALTER TABLE timers ADD COLUMN notes TEXT NOT NULL DEFAULT '';
+    ALTER TABLE timers ADD COLUMN session_note TEXT NOT NULL DEFAULT '';
+    ALTER TABLE sessions ADD COLUMN note TEXT NOT NULL DEFAULT '';",
+    // 7: per timer time formats
+    "ALTER TABLE timers ADD COLUMN time_format TEXT;",
+];
+
+impl SqliteStorage {
+    fn connect(&self) -> Result<Connection, Box<dyn std::error::Error>> {
+        let conn = Connection::open(&self.path)?;
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            conn.execute_batch(&format!(
+                "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+                migration,
+                i + 1
+            ))?;
+        }
+        Ok(conn)
+    }
+
+    fn read_stored(tx: &Transaction) -> Result<HashMap<usize, Stored>, rusqlite::Error> {
+        // every timer row, so one without tags or sessions still gets purged
+        let mut stored: HashMap<usize, Stored> = tx
+            .prepare("SELECT timer_id FROM timers")?
+            .query_map([], |row| row.get(0))?
+            .map(|id| id.map(|id| (id, Stored::default())))
+            .collect::<Result<_, _>>()?;
+        let mut tags = tx.prepare("SELECT timer_id, tag FROM tags ORDER BY rowid")?;
+        let mut rows = tags.query([])?;
+        while let Some(row) = rows.next()? {
+            stored
+                .entry(row.get(0)?)
+                .or_default()
+                .tags
+                .push(row.get(1)?);
+        }
+        let mut sessions = tx.prepare(
+            "SELECT timer_id, start, end, adjustment, note FROM sessions ORDER BY start, rowid",
+        )?;
+        let mut rows = sessions.query([])?;
+        while let Some(row) = rows.next()? {
+            stored
+                .entry(row.get(0)?)
+                .or_default()
+                .sessions
+                .push(Session {
+                    start: row.get(1)?,
+                    end: row.get(2)?,
+                    adjustment: row.get(3)?,
+                    note: row.get(4)?,
+                });
+        }
+        Ok(stored)
+    }
+
+    fn meta(conn: &Connection, key: &str) -> Result<Option<u64>, rusqlite::Error> {
+        conn.query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| {
+            row.get(0)
+        })
+        .optional()
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self) -> Result<Option<PersistedState>, Box<dyn std::error::Error>> {
+        let conn = self.connect()?;
+        let Some(save_timestamp) = Self::meta(&conn, "save_timestamp")? else {
+            return self.fallback.load();
+        };
+
+        let rows = conn
+            .prepare(
+                "SELECT timer_id, label, elapsed_seconds, last_wall_clock, running, session_start,
+                        status, deleted_at, mode, target, color, project, notes, session_note,
+                        time_format
+                 FROM timers ORDER BY position",
+            )?
+            .query_map([], |row| {
+                let timer = PersistedTimer {
+                    timer_id: row.get(0)?,
+                    label: row.get(1)?,
+                    elapsed_seconds: row.get(2)?,
+                    last_wall_clock: row.get(3)?,
+                    running: row.get(4)?,
+                    session_start: row.get(5)?,
+                    sessions: Vec::new(),
+                    mode: Mode::parse(&row.get::<_, String>(8)?).unwrap_or_default(),
+                    target: row.get(9)?,
+                    color: row.get(10)?,
+                    tags: Vec::new(),
+                    project: row.get(11)?,
+                    notes: row.get(12)?,
+                    session_note: row.get(13)?,
+                    time_format: row.get(14)?,
+                };
+                Ok((
+                    timer,
+                    row.get::<_, String>(6)?,
+                    row.get::<_, Option<u64>>(7)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut sessions = conn.prepare(
+            "SELECT start, end, adjustment, note FROM sessions WHERE timer_id = ?1
+             ORDER BY start, rowid",
+        )?;
+        let mut tags = conn.prepare("SELECT tag FROM tags WHERE timer_id = ?1 ORDER BY rowid")?;
+        let mut timers = Vec::new();
+        let mut archive = Vec::new();
+        let mut trash = Vec::new();
+        let mut stored = HashMap::new();
+        for (mut timer, status, deleted_at) in rows {
+            timer.sessions = sessions
+                .query_map([timer.timer_id], |row| {
+                    Ok(Session {
+                        start: row.get(0)?,
+                        end: row.get(1)?,
+                        adjustment: row.get(2)?,
+                        note: row.get(3)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            timer.tags = tags
+                .query_map([timer.timer_id], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()?;
+            stored.insert(
+                timer.timer_id,
+                Stored {
+                    tags: timer.tags.clone(),
+                    sessions: timer.sessions.clone(),
+                },
+            );
+            match status.as_str() {
+                "archived" => archive.push(timer),
+                "trashed" => trash.push(TrashedTimer {
+                    deleted_at: deleted_at.unwrap_or(save_timestamp),
+                    timer,
+                }),
+                _ => timers.push(timer),
+            }
+        }
+
+        let switch_log = conn
+            .prepare(
+                "SELECT timestamp, from_timer, to_timer FROM events
+                 WHERE kind = 'switch' ORDER BY timestamp, rowid",
+            )?
+            .query_map([], |row| {
+                Ok(SwitchEvent {
+                    timestamp: row.get(0)?,
+                    from_timer: row.get(1)?,
+                    to_timer: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        *self.stored.borrow_mut() = Some(stored);
+
+        Ok(Some(PersistedState {
+            timers,
+            selected_timer: Self::meta(&conn, "selected_timer")?.unwrap_or(0) as usize,
+            save_timestamp,
+            exclusive_mode: Self::meta(&conn, "exclusive_mode")?.unwrap_or(0) != 0,
+            view: View::from_index(Self::meta(&conn, "view")?.unwrap_or(0) as usize),
+            switch_log,
+            archive,
+            trash,
+        }))
+    }
+
+    // upserts the timer rows and appends new sessions and events, a timer's sessions or tags
+    // are only rewritten when something already stored changed, like an edited note
+    fn save(&self, state: &PersistedState) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.connect()?;
+        let tx = conn.transaction()?;
+        // taken out so a failed save reads the database again next time
+        let mut stored = match self.stored.borrow_mut().take() {
+            Some(stored) => stored,
+            None => Self::read_stored(&tx)?,
+        };
+        {
+            let mut meta =
+                tx.prepare("INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)")?;
+            meta.execute(params!["selected_timer", state.selected_timer as u64])?;
+            meta.execute(params!["save_timestamp", state.save_timestamp])?;
+            meta.execute(params!["exclusive_mode", state.exclusive_mode])?;
+            meta.execute(params!["view", state.view.index() as u64])?;
+
+            let mut timers = tx.prepare(
+                "INSERT INTO timers
+                 (timer_id, position, label, elapsed_seconds, last_wall_clock, running, session_start,
+                  status, deleted_at, mode, target, color, project, notes, session_note, time_format)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+                 ON CONFLICT (timer_id) DO UPDATE SET
+                  position = excluded.position, label = excluded.label,
+                  elapsed_seconds = excluded.elapsed_seconds,
+                  last_wall_clock = excluded.last_wall_clock, running = excluded.running,
+                  session_start = excluded.session_start, status = excluded.status,
+                  deleted_at = excluded.deleted_at, mode = excluded.mode, target = excluded.target,
+                  color = excluded.color, project = excluded.project, notes = excluded.notes,
+                  session_note = excluded.session_note, time_format = excluded.time_format",
+            )?;
+            let mut tags = tx.prepare("INSERT INTO tags (timer_id, tag) VALUES (?1, ?2)")?;
+            let mut clear_tags = tx.prepare("DELETE FROM tags WHERE timer_id = ?1")?;
+            let mut sessions = tx.prepare(
+                "INSERT INTO sessions (timer_id, start, end, adjustment, note)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            let mut clear_sessions = tx.prepare("DELETE FROM sessions WHERE timer_id = ?1")?;
+            let rows = state
+                .timers
+                .iter()
+                .map(|timer| (timer, "active", None))
+                .chain(state.archive.iter().map(|timer| (timer, "archived", None)))
+                .chain(
+                    state
+                        .trash
+                        .iter()
+                        .map(|entry| (&entry.timer, "trashed", Some(entry.deleted_at))),
+                );
+            let mut saved = HashSet::new();
+            for (position, (timer, status, deleted_at)) in rows.enumerate() {
+                timers.execute(params![
+                    timer.timer_id,
+                    position,
+                    timer.label,
+                    timer.elapsed_seconds,
+                    timer.last_wall_clock,
+                    timer.running,
+                    timer.session_start,
+                    status,
+                    deleted_at,
+                    timer.mode.as_str(),
+                    timer.target,
+                    timer.color,
+                    timer.project,
+                    timer.notes,
+                    timer.session_note,
+                    timer.time_format,
+                ])?;
+                saved.insert(timer.timer_id);
+                let known = stored.entry(timer.timer_id).or_default();
+                if known.tags != timer.tags {
+                    clear_tags.execute([timer.timer_id])?;
+                    for tag in &timer.tags {
+                        tags.execute(params![timer.timer_id, tag])?;
+                    }
+                    known.tags = timer.tags.clone();
+                }
+                let new = if timer.sessions.starts_with(&known.sessions) {
+                    known.sessions.len()
+                } else {
+                    clear_sessions.execute([timer.timer_id])?;
+                    known.sessions.clear();
+                    0
+                };
+                for session in &timer.sessions[new..] {
+                    sessions.execute(params![
+                        timer.timer_id,
+                        session.start,
+                        session.end,
+                        session.adjustment,
+                        session.note
+                    ])?;
+                }
+                known.sessions.extend_from_slice(&timer.sessions[new..]);
+            }
+
+            // whatever wasn't saved got purged from the trash
+            let mut purge = tx.prepare("DELETE FROM timers WHERE timer_id = ?1")?;
+            for timer_id in stored.keys().filter(|id| !saved.contains(id)) {
+                purge.execute([timer_id])?;
+                clear_tags.execute([timer_id])?;
+                clear_sessions.execute([timer_id])?;
+            }
+            stored.retain(|id, _| saved.contains(id));
+
+            // the switch log only grows, unless an older file replaced it
+            let count: usize = tx.query_row(
+                "SELECT COUNT(*) FROM events WHERE kind = 'switch'",
+                [],
+                |row| row.get(0),
+            )?;
+            let new = if count <= state.switch_log.len() {
+                count
+            } else {
+                tx.execute("DELETE FROM events WHERE kind = 'switch'", [])?;
+                0
+            };
+            let mut events = tx.prepare(
+                "INSERT INTO events (timestamp, kind, from_timer, to_timer)
+                 VALUES (?1, 'switch', ?2, ?3)",
+            )?;
+            for event in &state.switch_log[new..] {
+                events.execute(params![event.timestamp, event.from_timer, event.to_timer])?;
+            }
+        }
+        tx.commit()?;
+        *self.stored.borrow_mut() = Some(stored);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::Template;
+    use std::path::Path;
+
+    // a fresh directory per test, tests run in parallel
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("stopwatch-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sqlite(dir: &Path) -> SqliteStorage {
+        SqliteStorage {
+            path: dir.join("timers.sqlite3"),
+            fallback: TomlStorage {
+                path: dir.join("timers.toml"),
+            },
+            stored: RefCell::new(None),
+        }
+    }
+
+    fn timer(timer_id: usize, label: &str) -> PersistedTimer {
+        let mut timer = Template::default().to_persisted(timer_id, 1000);
+        timer.label = Some(label.to_string());
+        timer
+    }
+
+    fn state() -> PersistedState {
+        let mut tagged = timer(1, "review");
+        tagged.tags = vec!["client".to_string()];
+        tagged.sessions = vec![Session::new(100, 200), Session::new(300, 400)];
+        let mut state = PersistedState::empty(1000);
+        state.timers = vec![tagged, timer(2, "bare")];
+        state.trash = vec![TrashedTimer {
+            timer: timer(3, "gone"),
+            deleted_at: 900,
+        }];
+        state
+    }
+
+    fn ids(timers: &[PersistedTimer]) -> Vec<usize> {
+        timers.iter().map(|timer| timer.timer_id).collect()
+    }
+
+    #[test]
+    fn sqlite_round_trip_with_purges() {
+        let dir = temp_dir("round-trip");
+        let mut state = state();
+        sqlite(&dir).save(&state).unwrap();
+
+        let loaded = sqlite(&dir).load().unwrap().unwrap();
+        assert_eq!(ids(&loaded.timers), vec![1, 2]);
+        assert_eq!(loaded.timers[0].tags, vec!["client"]);
+        assert_eq!(loaded.timers[0].sessions, state.timers[0].sessions);
+        assert_eq!(loaded.trash[0].timer.timer_id, 3);
+        assert_eq!(loaded.trash[0].deleted_at, 900);
+
+        // purged from the trash and deleted for good, saved without a load in between
+        state.trash.clear();
+        state.timers.remove(1);
+        state.timers[0].sessions.push(Session::new(500, 600));
+        sqlite(&dir).save(&state).unwrap();
+
+        let storage = sqlite(&dir);
+        let mut loaded = storage.load().unwrap().unwrap();
+        assert_eq!(ids(&loaded.timers), vec![1]);
+        assert!(loaded.trash.is_empty());
+        assert_eq!(loaded.timers[0].sessions.len(), 3);
+
+        // and through the cache a load fills
+        loaded.timers[0].sessions[0].note = "edited".to_string();
+        loaded.timers.push(timer(4, "new"));
+        storage.save(&loaded).unwrap();
+        loaded.timers.remove(1);
+        storage.save(&loaded).unwrap();
+        let reloaded = sqlite(&dir).load().unwrap().unwrap();
+        assert_eq!(ids(&reloaded.timers), vec![1]);
+        assert_eq!(reloaded.timers[0].sessions, loaded.timers[0].sessions);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn sqlite_takes_over_from_timers_toml() {
+        let dir = temp_dir("toml-to-sqlite");
+        let storage = sqlite(&dir);
+        assert!(storage.load().unwrap().is_none());
+        storage.fallback.save(&state()).unwrap();
+
+        // the first start with sqlite reads the toml file and saves it into the database
+        let state = storage.load().unwrap().unwrap();
+        assert_eq!(ids(&state.timers), vec![1, 2]);
+        storage.save(&state).unwrap();
+        fs::remove_file(dir.join("timers.toml")).unwrap();
+
+        let loaded = sqlite(&dir).load().unwrap().unwrap();
+        assert_eq!(ids(&loaded.timers), vec![1, 2]);
+        assert_eq!(loaded.timers[0].sessions, state.timers[0].sessions);
+        assert_eq!(loaded.trash[0].timer.label.as_deref(), Some("gone"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
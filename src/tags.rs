@@ -0,0 +1,157 @@
+// "#client", "@acme" or a bare word that may be either
+#[derive(Clone, PartialEq, Debug)]
+pub enum TagFilter {
+    Tag(String),
+    Project(String),
+    Any(String),
+}
+
+impl TagFilter {
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        if let Some(tag) = input.strip_prefix('#') {
+            Some(Self::Tag(tag.trim().to_string()))
+        } else if let Some(project) = input.strip_prefix('@') {
+            Some(Self::Project(project.trim().to_string()))
+        } else if input.is_empty() {
+            None
+        } else {
+            Some(Self::Any(input.to_string()))
+        }
+    }
+
+    pub fn matches(&self, tags: &[String], project: Option<&str>) -> bool {
+        let has_tag = |wanted: &str| tags.iter().any(|tag| tag.eq_ignore_ascii_case(wanted));
+        let in_project =
+            |wanted: &str| project.is_some_and(|project| project.eq_ignore_ascii_case(wanted));
+        match self {
+            Self::Tag(tag) => has_tag(tag),
+            Self::Project(project) => in_project(project),
+            Self::Any(value) => has_tag(value) || in_project(value),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Tag(tag) => format!("#{}", tag),
+            Self::Project(project) => format!("@{}", project),
+            Self::Any(value) => value.clone(),
+        }
+    }
+}
+
+// "client, #review  backend" -> ["client", "review", "backend"]
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for word in input.split(|c: char| c.is_whitespace() || c == ',') {
+        let tag = word.trim_start_matches('#');
+        if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+// longest completion of prefix that all matching candidates agree on, and whether it was unique
+fn extend<'a>(prefix: &str, candidates: &'a [String]) -> Option<(&'a str, bool)> {
+    let matching: Vec<&String> = candidates
+        .iter()
+        .filter(|candidate| {
+            candidate.len() > prefix.len()
+                && candidate.to_lowercase().starts_with(&prefix.to_lowercase())
+        })
+        .collect();
+    let first = matching.first()?;
+    let common = matching
+        .iter()
+        .skip(1)
+        .fold(first.as_str(), |common, other| {
+            let len = common
+                .char_indices()
+                .zip(other.chars())
+                .find(|((_, a), b)| !a.eq_ignore_ascii_case(b))
+                .map_or(common.len().min(other.len()), |((i, _), _)| i);
+            &common[..len]
+        });
+    (common.len() > prefix.len()).then_some((common, matching.len() == 1))
+}
+
+// tab completion for the last word of a tag list or filter, keeps a '#' or '@' in front
+pub fn complete_word(buffer: &str, candidates: &[String]) -> Option<String> {
+    let start = buffer
+        .rfind(|c: char| c.is_whitespace() || c == ',')
+        .map_or(0, |i| i + 1);
+    let (head, word) = buffer.split_at(start);
+    let sigil_len = word
+        .chars()
+        .next()
+        .filter(|c| *c == '#' || *c == '@')
+        .map_or(0, char::len_utf8);
+    let (sigil, prefix) = word.split_at(sigil_len);
+    let (completion, unique) = extend(prefix, candidates)?;
+    let space = if unique { " " } else { "" };
+    Some(format!("{}{}{}{}", head, sigil, completion, space))
+}
+
+// tab completion for a single value that may contain spaces, like a project name
+pub fn complete_value(buffer: &str, candidates: &[String]) -> Option<String> {
+    extend(buffer, candidates).map(|(completion, _)| completion.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_filters() {
+        assert_eq!(
+            TagFilter::parse(" #client "),
+            Some(TagFilter::Tag("client".into()))
+        );
+        assert_eq!(
+            TagFilter::parse("@acme"),
+            Some(TagFilter::Project("acme".into()))
+        );
+        assert_eq!(
+            TagFilter::parse("review"),
+            Some(TagFilter::Any("review".into()))
+        );
+        assert_eq!(TagFilter::parse("  "), None);
+    }
+
+    #[test]
+    fn filters_match_case_insensitively() {
+        let tags = vec!["Client".to_string()];
+        assert!(TagFilter::Tag("client".into()).matches(&tags, None));
+        assert!(!TagFilter::Tag("acme".into()).matches(&tags, Some("acme")));
+        assert!(TagFilter::Project("ACME".into()).matches(&[], Some("acme")));
+        assert!(TagFilter::Any("acme".into()).matches(&tags, Some("Acme")));
+        assert!(!TagFilter::Any("other".into()).matches(&tags, Some("acme")));
+    }
+
+    #[test]
+    fn parses_tag_lists() {
+        assert_eq!(
+            parse_tags("client, #review  backend,,Client"),
+            vec!["client", "review", "backend"]
+        );
+        assert!(parse_tags(" , # ").is_empty());
+    }
+
+    #[test]
+    fn completes_the_last_word() {
+        let candidates = vec![
+            "review".to_string(),
+            "research".to_string(),
+            "backend".to_string(),
+        ];
+        assert_eq!(
+            complete_word("client, #ba", &candidates),
+            Some("client, #backend ".into())
+        );
+        assert_eq!(complete_word("re", &candidates), None);
+        assert_eq!(complete_word("res", &candidates), Some("research ".into()));
+        assert_eq!(complete_value("Re", &candidates), None);
+        assert_eq!(complete_value("rev", &candidates), Some("review".into()));
+    }
+}
@@ -0,0 +1,201 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Padding},
+    Frame,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::str::FromStr;
+
+use crate::duration::parse_duration;
+use crate::timefmt::TimeFormat;
+use crate::PersistedTimer;
+
+const POMODORO_ROUND: u64 = 25 * 60;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Default, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mode {
+    #[default]
+    CountUp,
+    Countdown,
+    Pomodoro,
+}
+
+impl Mode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::CountUp => "count-up",
+            Self::Countdown => "countdown",
+            Self::Pomodoro => "pomodoro",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "count-up" => Some(Self::CountUp),
+            "countdown" => Some(Self::Countdown),
+            "pomodoro" => Some(Self::Pomodoro),
+            _ => None,
+        }
+    }
+
+    // length of one pomodoro round, the target if one is set
+    pub fn round_length(target: Option<u64>) -> u64 {
+        target.filter(|t| *t > 0).unwrap_or(POMODORO_ROUND)
+    }
+
+    // elapsed seconds at which the timer should stop next, None when it never does
+    pub fn next_alert(self, target: Option<u64>, total: u64) -> Option<u64> {
+        match self {
+            Self::CountUp => None,
+            Self::Countdown => target.filter(|t| total < *t),
+            Self::Pomodoro => {
+                let round = Self::round_length(target);
+                Some((total / round + 1) * round)
+            }
+        }
+    }
+}
+
+// a named preset from config.toml, `[[templates]]`, the default is a blank count-up timer
+#[derive(Clone, Deserialize, Default, Debug)]
+pub struct Template {
+    pub name: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub mode: Mode,
+    #[serde(default, deserialize_with = "target_seconds")]
+    pub target: Option<u64>, // "15m", "1h30m", "25:00"
+    #[serde(default, deserialize_with = "color_name")]
+    pub color: Option<String>, // anything ratatui understands, "red", "lightblue", "#ff8800"
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub time_format: Option<TimeFormat>, // overrides the one in config.toml for these timers
+}
+
+fn target_seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse_duration(&value)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+fn color_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    Color::from_str(&value)
+        .map(|_| Some(value.clone()))
+        .map_err(|_| serde::de::Error::custom(format!("unknown color '{}'", value)))
+}
+
+pub fn parse_color(name: &Option<String>) -> Option<Color> {
+    name.as_deref().and_then(|name| Color::from_str(name).ok())
+}
+
+impl Template {
+    // a fresh running timer set up the way the template says
+    pub fn to_persisted(&self, timer_id: usize, now: u64) -> PersistedTimer {
+        PersistedTimer {
+            timer_id,
+            elapsed_seconds: 0,
+            last_wall_clock: now,
+            label: self
+                .label
+                .clone()
+                .or_else(|| Some(self.name.clone()).filter(|name| !name.is_empty())),
+            running: true,
+            session_start: Some(now),
+            sessions: Vec::new(),
+            mode: self.mode,
+            target: self.target,
+            color: self.color.clone(),
+            tags: self.tags.clone(),
+            project: self.project.clone(),
+            notes: String::new(),
+            session_note: String::new(),
+            time_format: self.time_format.as_ref().map(|format| format.as_str().to_string()),
+        }
+    }
+
+    // `format` is the one from config.toml, the template's own goes first
+    fn describe(&self, format: Option<&TimeFormat>) -> String {
+        let mut parts = vec![self.mode.as_str().to_string()];
+        if let Some(target) = self.target {
+            parts.push(crate::report::duration_text(
+                target,
+                self.time_format.as_ref().or(format),
+            ));
+        }
+        parts.extend(self.project.iter().map(|project| format!("@{}", project)));
+        parts.extend(self.tags.iter().map(|tag| format!("#{}", tag)));
+        format!("{:<16} {}", self.name, parts.join(" "))
+    }
+}
+
+pub fn find<'a>(templates: &'a [Template], name: &str) -> Option<&'a Template> {
+    templates
+        .iter()
+        .find(|template| template.name.eq_ignore_ascii_case(name))
+}
+
+// row 0 is a plain count-up timer, the templates follow
+pub struct Picker {
+    pub selected: usize,
+}
+
+impl Picker {
+    pub fn new() -> Self {
+        Self { selected: 0 }
+    }
+
+    pub fn move_selection(&mut self, delta: isize, templates: &[Template]) {
+        self.selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(templates.len());
+    }
+
+    pub fn chosen<'a>(&self, templates: &'a [Template]) -> Option<&'a Template> {
+        self.selected.checked_sub(1).and_then(|i| templates.get(i))
+    }
+
+    pub fn draw(&self, frame: &mut Frame, templates: &[Template], format: Option<&TimeFormat>) {
+        let area = frame.area();
+        let mut items = vec![ListItem::new("Blank timer")];
+        items.extend(templates.iter().map(|template| {
+            let style = parse_color(&template.color)
+                .map(|color| Style::default().fg(color))
+                .unwrap_or_default();
+            ListItem::new(template.describe(format)).style(style)
+        }));
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Green))
+                    .title(" New timer ")
+                    .title_bottom(Line::from(" enter add · esc cancel ").right_aligned())
+                    .padding(Padding::horizontal(1)),
+            )
+            .style(Style::default().fg(Color::Gray).bg(Color::Black))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut list_state = ListState::default();
+        list_state.select(Some(self.selected));
+
+        let width = (area.width / 2).max(40).min(area.width);
+        let height = (templates.len() as u16 + 3).min(area.height);
+        let picker_area = Rect::new(
+            (area.width - width) / 2,
+            (area.height - height) / 2,
+            width,
+            height,
+        );
+        frame.render_widget(Clear, picker_area);
+        frame.render_stateful_widget(list, picker_area, &mut list_state);
+    }
+}
@@ -0,0 +1,264 @@
+use serde::Deserialize;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Unit {
+    Days,
+    Hours,
+    Minutes,
+    Seconds,
+}
+
+impl Unit {
+    fn seconds(self) -> u64 {
+        match self {
+            Self::Days => 86400,
+            Self::Hours => 3600,
+            Self::Minutes => 60,
+            Self::Seconds => 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Part {
+    Literal(String),
+    Unit(Unit, usize), // zero padded to this many digits
+    DecimalHours(usize), // total hours with this many decimals
+}
+
+// a display template like "%Dd %H:%M:%S" or "HH:MM:SS"; the biggest unit in it takes
+// everything above, so "%H:%M" keeps counting past 24 hours. `~` in front drops leading
+// units while they are zero, "~%Dd %H:%M:%S" shows "05:12" for five minutes
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct TimeFormat {
+    template: String,
+    parts: Vec<Part>,
+    hide_zero: bool,
+}
+
+impl TryFrom<String> for TimeFormat {
+    type Error = String;
+
+    fn try_from(template: String) -> Result<Self, String> {
+        Self::parse(&template)
+    }
+}
+
+// %D days, %H %M %S two digits, %h %m %s as they are, %T hours with two decimals (%.1T for one)
+fn percent_parts(template: &str) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            literal.push(c);
+            continue;
+        }
+        let mut decimals = None;
+        if chars.peek() == Some(&'.') {
+            chars.next();
+            decimals = chars.next().and_then(|d| d.to_digit(10)).map(|d| d as usize);
+            if decimals.is_none() {
+                return Err("%. needs a digit, like %.1T".to_string());
+            }
+        }
+        let part = match (chars.next(), decimals) {
+            (Some('%'), None) => {
+                literal.push('%');
+                continue;
+            }
+            (Some('T'), decimals) => Part::DecimalHours(decimals.unwrap_or(2)),
+            (Some('D' | 'd'), None) => Part::Unit(Unit::Days, 1),
+            (Some('H'), None) => Part::Unit(Unit::Hours, 2),
+            (Some('h'), None) => Part::Unit(Unit::Hours, 1),
+            (Some('M'), None) => Part::Unit(Unit::Minutes, 2),
+            (Some('m'), None) => Part::Unit(Unit::Minutes, 1),
+            (Some('S'), None) => Part::Unit(Unit::Seconds, 2),
+            (Some('s'), None) => Part::Unit(Unit::Seconds, 1),
+            (Some(other), _) => {
+                return Err(format!("unknown %{} in time format, use %D %H %M %S or %T", other))
+            }
+            (None, _) => return Err("time format ends in a lone %".to_string()),
+        };
+        if !literal.is_empty() {
+            parts.push(Part::Literal(std::mem::take(&mut literal)));
+        }
+        parts.push(part);
+    }
+    if !literal.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+    Ok(parts)
+}
+
+// "HH:MM:SS", "H:MM", "DDd HH:MM"; runs of D H M S are units, as wide as the run
+fn letter_parts(template: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        let unit = match c {
+            'D' => Unit::Days,
+            'H' => Unit::Hours,
+            'M' => Unit::Minutes,
+            'S' => Unit::Seconds,
+            c => {
+                match parts.last_mut() {
+                    Some(Part::Literal(literal)) => literal.push(c),
+                    _ => parts.push(Part::Literal(c.to_string())),
+                }
+                continue;
+            }
+        };
+        let mut width = 1;
+        while chars.peek() == Some(&c) {
+            chars.next();
+            width += 1;
+        }
+        parts.push(Part::Unit(unit, width));
+    }
+    parts
+}
+
+impl TimeFormat {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let (hide_zero, rest) = match template.strip_prefix('~') {
+            Some(rest) => (true, rest),
+            None => (false, template),
+        };
+        let parts = if rest.contains('%') {
+            percent_parts(rest)?
+        } else {
+            letter_parts(rest)
+        };
+        if !parts.iter().any(|part| !matches!(part, Part::Literal(_))) {
+            return Err(format!(
+                "time format '{}' shows no time, use %D %H %M %S %T or HH:MM:SS",
+                template
+            ));
+        }
+        Ok(Self {
+            template: template.to_string(),
+            parts,
+            hide_zero,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    fn units(&self) -> impl Iterator<Item = Unit> + '_ {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Unit(unit, _) => Some(*unit),
+            _ => None,
+        })
+    }
+
+    // what a unit shows, wrapped at the next bigger unit in the template if there is one
+    fn value(&self, unit: Unit, seconds: u64) -> u64 {
+        let value = seconds / unit.seconds();
+        match self
+            .units()
+            .filter(|other| other.seconds() > unit.seconds())
+            .map(Unit::seconds)
+            .min()
+        {
+            Some(bigger) => value % (bigger / unit.seconds()),
+            None => value,
+        }
+    }
+
+    pub fn format(&self, seconds: u64) -> String {
+        let mut parts: Vec<&Part> = self.parts.iter().collect();
+        if self.hide_zero {
+            // drop zero units up to the last one, together with the text after each;
+            // text in front of the first unit stays
+            loop {
+                let units: Vec<usize> = (0..parts.len())
+                    .filter(|i| !matches!(parts[*i], Part::Literal(_)))
+                    .collect();
+                match (units.first(), units.get(1)) {
+                    (Some(&first), Some(&next)) => match parts[first] {
+                        Part::Unit(unit, _) if self.value(*unit, seconds) == 0 => {
+                            parts.drain(first..next);
+                        }
+                        _ => break,
+                    },
+                    _ => break,
+                }
+            }
+        }
+        parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) => text.clone(),
+                Part::Unit(unit, width) => {
+                    format!("{:0width$}", self.value(*unit, seconds), width = *width)
+                }
+                Part::DecimalHours(decimals) => format!(
+                    "{:.decimals$}",
+                    seconds as f64 / 3600.0,
+                    decimals = *decimals
+                ),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(template: &str, seconds: u64) -> String {
+        TimeFormat::parse(template).unwrap().format(seconds)
+    }
+
+    #[test]
+    fn formats_percent_templates() {
+        assert_eq!(format("%Dd %H:%M:%S", 93784), "1d 02:03:04");
+        assert_eq!(format("%h:%m:%s", 3723), "1:2:3");
+        assert_eq!(format("100%% %M", 60), "100% 01");
+    }
+
+    #[test]
+    fn formats_letter_templates() {
+        assert_eq!(format("HH:MM:SS", 3723), "01:02:03");
+        assert_eq!(format("H:MM", 3723), "1:02");
+        assert_eq!(format("DDd HH:MM", 93784), "01d 02:03");
+    }
+
+    #[test]
+    fn biggest_unit_keeps_counting() {
+        assert_eq!(format("%H:%M", 90000), "25:00");
+        assert_eq!(format("HH:MM:SS", 360000), "100:00:00");
+        assert_eq!(format("%Dd %H:%M", 90000), "1d 01:00");
+    }
+
+    #[test]
+    fn decimal_hours() {
+        assert_eq!(format("%T", 5400), "1.50");
+        assert_eq!(format("%.1T h", 5400), "1.5 h");
+        assert_eq!(format("%.0T", 5400), "2");
+    }
+
+    #[test]
+    fn tilde_hides_leading_zero_units() {
+        assert_eq!(format("~%Dd %H:%M:%S", 312), "05:12");
+        assert_eq!(format("~%Dd %H:%M:%S", 3912), "01:05:12");
+        assert_eq!(format("~%Dd %H:%M:%S", 90000), "1d 01:00:00");
+        // the last unit always shows
+        assert_eq!(format("~%Dd %H:%M:%S", 0), "00");
+        assert_eq!(format("~[%H:%M]", 60), "[01]");
+    }
+
+    #[test]
+    fn rejects_bad_templates() {
+        assert!(TimeFormat::parse("%X").is_err());
+        assert!(TimeFormat::parse("%H:%").is_err());
+        assert!(TimeFormat::parse("%.T").is_err());
+        assert!(TimeFormat::parse("%.1H").is_err());
+        assert!(TimeFormat::parse("time").is_err());
+        assert!(TimeFormat::parse("~").is_err());
+    }
+}
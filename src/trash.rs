@@ -0,0 +1,173 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, ListState, Padding},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::report::{self, duration_text};
+use crate::timefmt::TimeFormat;
+use crate::PersistedTimer;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct TrashedTimer {
+    pub timer: PersistedTimer,
+    pub deleted_at: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Shelf {
+    Archive,
+    Trash,
+}
+
+// which list is focused in the archive/trash view and the highlighted row
+pub struct ShelfView {
+    pub shelf: Shelf,
+    pub selected: usize,
+}
+
+impl ShelfView {
+    pub fn new() -> Self {
+        Self {
+            shelf: Shelf::Archive,
+            selected: 0,
+        }
+    }
+
+    pub fn switch_shelf(&mut self) {
+        self.shelf = match self.shelf {
+            Shelf::Archive => Shelf::Trash,
+            Shelf::Trash => Shelf::Archive,
+        };
+        self.selected = 0;
+    }
+
+    pub fn move_selection(&mut self, delta: isize, len: usize) {
+        if len == 0 {
+            self.selected = 0;
+        } else {
+            self.selected = self.selected.saturating_add_signed(delta).min(len - 1);
+        }
+    }
+}
+
+// drop trash older than `days`, 0 keeps everything
+pub fn expire(trash: &mut Vec<TrashedTimer>, days: u64, now: u64) -> usize {
+    if days == 0 {
+        return 0;
+    }
+    let before = trash.len();
+    let cutoff = now.saturating_sub(days * 86400);
+    trash.retain(|entry| entry.deleted_at >= cutoff);
+    before - trash.len()
+}
+
+fn tracked_seconds(timer: &PersistedTimer) -> u64 {
+    timer
+        .sessions
+        .iter()
+        .map(|s| s.end.saturating_sub(s.start) as i64 + s.adjustment)
+        .sum::<i64>()
+        .max(0) as u64
+}
+
+// in the timer's own time format when it still parses, like on the grid
+fn row(timer: &PersistedTimer, when: u64, format: Option<&TimeFormat>) -> ListItem<'static> {
+    let own = timer
+        .time_format
+        .as_deref()
+        .and_then(|format| TimeFormat::parse(format).ok());
+    ListItem::new(format!(
+        "{:<24} {:>12} tracked   {}",
+        report::timer_label(timer),
+        duration_text(tracked_seconds(timer), own.as_ref().or(format)),
+        report::local_date(when).format("%Y-%m-%d")
+    ))
+}
+
+fn draw_list(
+    frame: &mut Frame,
+    area: ratatui::layout::Rect,
+    title: &str,
+    items: Vec<ListItem<'static>>,
+    selected: Option<usize>,
+) {
+    let focused = selected.is_some();
+    let empty = items.is_empty();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(if focused { Color::Green } else { Color::Gray }))
+        .title(format!(" {} ", title))
+        .padding(Padding::horizontal(1));
+    let items = if empty {
+        vec![ListItem::new("empty")]
+    } else {
+        items
+    };
+    let list = List::new(items)
+        .block(block)
+        .style(Style::default().fg(Color::Gray))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut list_state = ListState::default();
+    list_state.select(selected.filter(|_| !empty));
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+pub fn draw(
+    frame: &mut Frame,
+    view: &ShelfView,
+    archive: &[PersistedTimer],
+    trash: &[TrashedTimer],
+    trash_days: u64,
+    format: Option<&TimeFormat>,
+) {
+    let area = frame.area();
+    let outer = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Green))
+        .title(" Archive / Trash ")
+        .title_bottom(
+            Line::from(" tab switch · enter restore · del purge · esc close ").right_aligned(),
+        );
+    let inner = outer.inner(area);
+    frame.render_widget(outer, area);
+    let halves = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(inner);
+
+    let selected_in = |shelf| (view.shelf == shelf).then_some(view.selected);
+    draw_list(
+        frame,
+        halves[0],
+        "Archived (last used)",
+        archive
+            .iter()
+            .map(|timer| row(timer, timer.last_wall_clock, format))
+            .collect(),
+        selected_in(Shelf::Archive),
+    );
+    let trash_title = if trash_days == 0 {
+        "Trash (deleted)".to_string()
+    } else {
+        format!("Trash (deleted, purged after {} days)", trash_days)
+    };
+    draw_list(
+        frame,
+        halves[1],
+        &trash_title,
+        trash
+            .iter()
+            .map(|entry| row(&entry.timer, entry.deleted_at, format))
+            .collect(),
+        selected_in(Shelf::Trash),
+    );
+}
+
+// archived timers still count for reports and exports, trashed ones don't
+pub fn with_archived(timers: &[PersistedTimer], archive: &[PersistedTimer]) -> Vec<PersistedTimer> {
+    timers.iter().chain(archive).cloned().collect()
+}
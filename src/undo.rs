@@ -0,0 +1,67 @@
+use crate::session::Session;
+
+const HISTORY_LIMIT: usize = 50;
+
+// the part of a timer an action changed, as it was before; putting it back leaves everything
+// else alone, so sessions tracked since the action survive an undo
+pub enum Change {
+    Label(Option<String>),
+    Tags(Vec<String>),
+    Project(Option<String>),
+    Color(Option<String>),
+    Target(Option<u64>),
+    Notes(String),
+    // found by start and adjustment, whether the session is still open or not
+    SessionNote {
+        start: u64,
+        adjustment: i64,
+        note: String,
+    },
+    // seconds to add back, with sessions the action removed and the ones it recorded
+    Time {
+        seconds: i64,
+        add: Vec<Session>,
+        remove: Vec<Session>,
+    },
+    Deleted(usize), // in the trash, comes back at this position
+    Undeleted,      // back on the grid, goes to the trash again
+    Order(Vec<usize>), // timer ids in grid order
+}
+
+pub struct UndoEntry {
+    pub description: String,           // "Timer 3 reset"
+    pub changes: Vec<(usize, Change)>, // timer_id and what to put back
+}
+
+#[derive(Default)]
+pub struct UndoHistory {
+    undo: Vec<UndoEntry>,
+    redo: Vec<UndoEntry>,
+}
+
+impl UndoHistory {
+    // a fresh action makes everything that was undone unreachable
+    pub fn record(&mut self, entry: UndoEntry) {
+        self.push_undo(entry);
+        self.redo.clear();
+    }
+
+    pub fn push_undo(&mut self, entry: UndoEntry) {
+        self.undo.push(entry);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn push_redo(&mut self, entry: UndoEntry) {
+        self.redo.push(entry);
+    }
+
+    pub fn pop_undo(&mut self) -> Option<UndoEntry> {
+        self.undo.pop()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<UndoEntry> {
+        self.redo.pop()
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+// how the timers are laid out, `v` cycles through them and the choice is saved with the timers
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum View {
+    #[default]
+    Grid, // a box per timer
+    Table,   // a row per timer with id, label, time, state and tags
+    Focus,   // only the selected timer, full screen
+    Sidebar, // a list of timers next to the selected one
+}
+
+impl View {
+    pub const ALL: [View; 4] = [Self::Grid, Self::Table, Self::Focus, Self::Sidebar];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Grid => "grid",
+            Self::Table => "table",
+            Self::Focus => "focus",
+            Self::Sidebar => "sidebar",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        Self::ALL.into_iter().find(|view| view.name() == name)
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+
+    // the sqlite meta table only holds numbers
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|view| *view == self).unwrap_or(0)
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        Self::ALL.get(index).copied().unwrap_or_default()
+    }
+}